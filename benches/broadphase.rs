@@ -0,0 +1,125 @@
+// Times the grid broadphase against testing every pair, the way the hit
+// systems used to.
+//
+// Run with `cargo bench`.
+
+#[path = "../src/grid.rs"]
+#[allow(dead_code)]
+mod grid;
+
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+use grid::SpatialGrid;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+const PLAYFIELD: (f32, f32) = (480.0, 640.0);
+const TARGET_SIZE: (f32, f32) = (24.0, 16.0);
+const BULLET_SIZE: (f32, f32) = (2.0, 16.0);
+const CELL_SIZE: f32 = 64.0;
+/// Pair tests per measurement for the brute-force path, spread over the runs.
+const WORK: usize = 20_000_000;
+
+struct Scene {
+    targets: Vec<(Entity, Vec3)>,
+    bullets: Vec<Vec3>,
+}
+
+impl Scene {
+    /// `count` targets and as many bullets, scattered over the playfield.
+    fn random(count: usize, rng: &mut impl Rng) -> Self {
+        let mut position = || {
+            let (w, h) = PLAYFIELD;
+            Vec3::new(
+                rng.gen_range(-w / 2.0..w / 2.0),
+                rng.gen_range(-h / 2.0..h / 2.0),
+                0.0,
+            )
+        };
+
+        let targets = (0..count)
+            .map(|i| (Entity::from_raw(i as u32), position()))
+            .collect();
+        let bullets = (0..count).map(|_| position()).collect();
+
+        Self { targets, bullets }
+    }
+
+    fn brute_force(&self) -> usize {
+        let (bullet_size, target_size) = (Vec2::from(BULLET_SIZE), Vec2::from(TARGET_SIZE));
+        let mut hits = 0;
+
+        for bullet in &self.bullets {
+            for (_, target) in &self.targets {
+                if collide(*bullet, bullet_size, *target, target_size).is_some() {
+                    hits += 1;
+                }
+            }
+        }
+
+        hits
+    }
+
+    /// Rebuilds the grid then looks up every bullet, as one tick does.
+    fn broadphase(&self, grid: &mut SpatialGrid) -> usize {
+        let (bullet_size, target_size) = (Vec2::from(BULLET_SIZE), Vec2::from(TARGET_SIZE));
+
+        grid.clear();
+        for (entity, target) in &self.targets {
+            grid.insert(*entity, *target, target_size);
+        }
+
+        let mut hits = 0;
+
+        for bullet in &self.bullets {
+            for entity in grid.query(*bullet, bullet_size) {
+                let target = self.targets[entity.id() as usize].1;
+
+                if collide(*bullet, bullet_size, target, target_size).is_some() {
+                    hits += 1;
+                }
+            }
+        }
+
+        hits
+    }
+}
+
+/// Average time of `run` over `runs` runs, with its last result.
+fn time(runs: usize, mut run: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut result = run();
+    let start = Instant::now();
+
+    for _ in 0..runs {
+        result = run();
+    }
+
+    (start.elapsed() / runs as u32, result)
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    println!(
+        "{:>8} {:>14} {:>14} {:>8}",
+        "entities", "brute force", "grid", "speedup"
+    );
+
+    for count in [10, 100, 1000] {
+        let scene = Scene::random(count, &mut rng);
+        let mut grid = SpatialGrid::new(CELL_SIZE);
+        let runs = (WORK / (count * count)).max(1);
+
+        let (brute, brute_hits) = time(runs, || scene.brute_force());
+        let (broad, broad_hits) = time(runs, || scene.broadphase(&mut grid));
+
+        assert_eq!(brute_hits, broad_hits, "the grid missed or made up hits");
+
+        println!(
+            "{:>8} {:>14?} {:>14?} {:>7.1}x",
+            count,
+            brute,
+            broad,
+            brute.as_secs_f64() / broad.as_secs_f64()
+        );
+    }
+}
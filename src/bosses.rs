@@ -0,0 +1,397 @@
+use crate::{
+    collision::{Collider, Layer, Shape},
+    config::{AttackPattern, BossMovement, GameConfig},
+    despawn_all,
+    enemies::{fan, ring, spawn_enemy_bullet},
+    health::{Damage, DeathEvent, Health, WeakSpot},
+    player::Player,
+    scores::Scorer,
+    sim::{Interpolated, SimTime, SIMULATION},
+    waves::WaveManager,
+    GameState, Materials, WinSize,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::f32::consts::TAU;
+
+/// Width of a laser while it warms up, as a fraction of its live width.
+const LASER_WARMUP_WIDTH: f32 = 0.25;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            SIMULATION,
+            SystemSet::new()
+                .with_system(spawn_boss)
+                .with_system(boss_movement)
+                .with_system(weak_point_follow)
+                .with_system(boss_attack)
+                .with_system(laser_update)
+                .with_system(boss_death),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing)
+                .with_system(despawn_all::<Boss>)
+                .with_system(despawn_all::<WeakPoint>)
+                .with_system(despawn_all::<Laser>),
+        );
+    }
+}
+
+// -- Components --
+/// A boss of the kind at `def` in the config's `bosses.roster`.
+#[derive(Component)]
+pub struct Boss {
+    def: usize,
+    spawned_at: u64,
+    phase: usize,
+    phase_started: u64,
+}
+
+/// Spot on a boss, kept at `offset` from it.
+#[derive(Component)]
+pub struct WeakPoint {
+    boss: Entity,
+    offset: Vec2,
+}
+
+/// Beam fired straight down by `boss`.
+#[derive(Component)]
+pub struct Laser {
+    boss: Entity,
+    started: u64,
+    warmup: u64,
+    duration: u64,
+    width: f32,
+    velocity: f32,
+    damage: u32,
+}
+
+/// Weak points and lasers, each belonging to a boss.
+#[derive(SystemParam)]
+struct BossParts<'w, 's> {
+    weak_points: Query<'w, 's, (Entity, &'static WeakPoint)>,
+    lasers: Query<'w, 's, (Entity, &'static Laser)>,
+}
+
+impl<'w, 's> BossParts<'w, 's> {
+    /// Everything belonging to `boss`.
+    fn of(&self, boss: Entity) -> Vec<Entity> {
+        let weak_points = self
+            .weak_points
+            .iter()
+            .filter(|(_, weak_point)| weak_point.boss == boss)
+            .map(|(entity, _)| entity);
+        let lasers = self
+            .lasers
+            .iter()
+            .filter(|(_, laser)| laser.boss == boss)
+            .map(|(entity, _)| entity);
+
+        weak_points.chain(lasers).collect()
+    }
+}
+
+impl Laser {
+    fn is_live(&self, now: u64) -> bool {
+        now >= self.started + self.warmup
+    }
+
+    fn is_over(&self, now: u64) -> bool {
+        now >= self.started + self.warmup + self.duration
+    }
+}
+
+fn spawn_boss(
+    mut commands: Commands,
+    mut waves: ResMut<WaveManager>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    let now = sim_time.ticks();
+    let index = match waves.take_boss(now) {
+        Some(index) => index,
+        None => return,
+    };
+
+    let def = &config.bosses.roster[index];
+    // Enter from above; the movement brings it down into place.
+    let translation = Vec3::new(0.0, win_size.h / 2.0 + def.size.1, 10.0);
+
+    let boss = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(def.size)),
+                color: def.color(),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Boss {
+            def: index,
+            spawned_at: now,
+            phase: 0,
+            phase_started: now,
+        })
+        .insert(Health::new(def.health))
+        .insert(Collider::new(
+            Shape::from_config(def.hitbox, def.size),
+            Layer::Boss,
+        ))
+        .insert(Interpolated::new(translation))
+        .id();
+
+    for weak_point in &def.weak_points {
+        let offset = Vec2::from(weak_point.offset);
+        let translation = translation + offset.extend(1.0);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::from(weak_point.size)),
+                    color: materials.weak_point,
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(WeakPoint { boss, offset })
+            .insert(WeakSpot {
+                target: boss,
+                multiplier: weak_point.multiplier,
+            })
+            .insert(Collider::new(
+                Shape::Aabb(Vec2::from(weak_point.size)),
+                Layer::WeakPoint,
+            ))
+            .insert(Interpolated::new(translation));
+    }
+}
+
+/// Offset from the hover point along the path of `movement`, `t` ticks in.
+fn path_offset(movement: BossMovement, t: f32) -> Vec2 {
+    match movement {
+        BossMovement::Hover => Vec2::ZERO,
+        BossMovement::Sweep { width, period } => {
+            Vec2::new(width / 2.0 * (TAU * t / period as f32).sin(), 0.0)
+        }
+        BossMovement::FigureEight {
+            width,
+            height,
+            period,
+        } => {
+            let angle = TAU * t / period as f32;
+            Vec2::new(
+                width / 2.0 * angle.sin(),
+                height / 2.0 * (2.0 * angle).sin(),
+            )
+        }
+    }
+}
+
+fn boss_movement(
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    mut query: Query<(&mut Boss, &Health, &mut Transform)>,
+) {
+    let now = sim_time.ticks();
+    let dt = sim_time.dt();
+
+    for (mut boss, health, mut transform) in query.iter_mut() {
+        let def = &config.bosses.roster[boss.def];
+
+        let phase = def.phase(health.fraction());
+        if phase != boss.phase {
+            boss.phase = phase;
+            boss.phase_started = now;
+        }
+
+        // Head for the current point of the path at top speed, so phase
+        // changes and the entry glide instead of jumping.
+        let t = (now - boss.spawned_at) as f32;
+        let hover = Vec2::new(0.0, win_size.h / 2.0 * def.hover_at);
+        let target = hover + path_offset(def.phases[phase].movement, t);
+
+        let position = transform.translation.truncate();
+        let max_step = def.speed * dt;
+        let position = if position.distance(target) <= max_step {
+            target
+        } else {
+            position + (target - position).normalize() * max_step
+        };
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+fn weak_point_follow(
+    boss_query: Query<&Transform, With<Boss>>,
+    mut query: Query<(&WeakPoint, &mut Transform), Without<Boss>>,
+) {
+    for (weak_point, mut transform) in query.iter_mut() {
+        if let Ok(boss_tf) = boss_query.get(weak_point.boss) {
+            transform.translation = boss_tf.translation + weak_point.offset.extend(1.0);
+        }
+    }
+}
+
+fn boss_attack(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    win_size: Res<WinSize>,
+    player_query: Query<&Transform, With<Player>>,
+    query: Query<(Entity, &Boss, &Transform)>,
+) {
+    let now = sim_time.ticks();
+    let speed = config.enemies.bullet_speed;
+    let target = player_query
+        .get_single()
+        .map_or(Vec2::new(0.0, -win_size.h / 2.0), |player_tf| {
+            player_tf.translation.truncate()
+        });
+
+    for (entity, boss, transform) in query.iter() {
+        let def = &config.bosses.roster[boss.def];
+        let origin = transform.translation;
+        let elapsed = now - boss.phase_started;
+
+        for attack in &def.phases[boss.phase].attacks {
+            if elapsed == 0 || elapsed % attack.interval != 0 {
+                continue;
+            }
+
+            match attack.pattern {
+                AttackPattern::Aimed { count, spread } => {
+                    let aim = target - origin.truncate();
+
+                    for heading in fan(aim.y.atan2(aim.x), count, spread) {
+                        let velocity = heading * speed;
+                        spawn_enemy_bullet(&mut commands, &config, &materials, origin, velocity);
+                    }
+                }
+                AttackPattern::Radial { count } => {
+                    // Every other burst is turned by half a gap, so there's no safe spot.
+                    let turn = (elapsed / attack.interval % 2) as f32 / 2.0;
+
+                    for heading in ring(count, TAU * turn / count as f32) {
+                        let velocity = heading * speed;
+                        spawn_enemy_bullet(&mut commands, &config, &materials, origin, velocity);
+                    }
+                }
+                AttackPattern::Laser {
+                    width,
+                    warmup,
+                    duration,
+                    sweep,
+                    damage,
+                } => {
+                    // The beam reaches from the boss to the bottom of the playfield.
+                    let bottom = -win_size.h / 2.0;
+                    let height = origin.y - bottom;
+                    let translation = Vec3::new(origin.x, bottom + height / 2.0, 5.0);
+
+                    commands
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(width * LASER_WARMUP_WIDTH, height)),
+                                color: materials.laser_warmup,
+                                ..Default::default()
+                            },
+                            transform: Transform {
+                                translation,
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                        .insert(Laser {
+                            boss: entity,
+                            started: now,
+                            warmup,
+                            duration,
+                            width,
+                            velocity: (target.x - origin.x).signum() * sweep,
+                            damage,
+                        })
+                        .insert(Interpolated::new(translation));
+                }
+            }
+        }
+    }
+}
+
+fn laser_update(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    materials: Res<Materials>,
+    mut query: Query<(
+        Entity,
+        &Laser,
+        &mut Transform,
+        &mut Sprite,
+        Option<&Collider>,
+    )>,
+) {
+    let now = sim_time.ticks();
+
+    for (entity, laser, mut transform, mut sprite, collider) in query.iter_mut() {
+        if laser.is_over(now) {
+            commands.entity(entity).despawn();
+        } else if laser.is_live(now) {
+            transform.translation.x += laser.velocity * sim_time.dt();
+
+            if let Some(size) = sprite.custom_size.as_mut() {
+                size.x = laser.width;
+
+                // Harmless while it warms up.
+                if collider.is_none() {
+                    let collider = Collider::new(Shape::Aabb(*size), Layer::Laser);
+                    commands
+                        .entity(entity)
+                        .insert(collider.hits(&[Layer::Player]))
+                        .insert(Damage(laser.damage));
+                }
+            }
+            sprite.color = materials.laser;
+        }
+    }
+}
+
+/// Clears up after a defeated boss and moves the waves on.
+fn boss_death(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    mut waves: ResMut<WaveManager>,
+    mut scorer: Scorer,
+    config: Res<GameConfig>,
+    parts: BossParts,
+    boss_query: Query<&Boss>,
+) {
+    for death in deaths.iter() {
+        let boss = match boss_query.get(death.entity) {
+            Ok(boss) => boss,
+            Err(_) => continue,
+        };
+
+        for part in parts.of(death.entity) {
+            commands.entity(part).despawn();
+        }
+
+        scorer.award(config.bosses.roster[boss.def].score);
+        waves.boss_defeated();
+    }
+}
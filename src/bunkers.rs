@@ -0,0 +1,153 @@
+use crate::{
+    collision::{Collider, CollisionEvent, Layer, Shape},
+    config::GameConfig,
+    despawn_all,
+    sim::SIMULATION,
+    GameState, Materials, WinSize,
+};
+
+use bevy::prelude::*;
+
+/// Destructible cover between the player and the enemies, eroded cell by cell.
+pub struct BunkerPlugin;
+
+impl Plugin for BunkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_bunkers))
+            .add_system_to_stage(SIMULATION, bunker_hit)
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_all::<BunkerCell>),
+            );
+    }
+}
+
+// -- Components --
+/// One cell of a bunker, with the hits it takes before breaking.
+#[derive(Component)]
+pub struct BunkerCell(u32);
+
+fn spawn_bunkers(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    let layout = config.bunkers.layout(config.mode);
+    let cells = layout.cells();
+    let y = -win_size.h / 2.0 + layout.height;
+
+    for i in 0..layout.count {
+        let x = -win_size.w / 2.0 + win_size.w * (i as f32 + 0.5) / layout.count as f32;
+
+        for offset in &cells {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::from(layout.cell_size)),
+                        color: materials.bunker,
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(x + offset.x, y + offset.y, 5.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(BunkerCell(layout.cell_health))
+                .insert(Collider::new(
+                    Shape::Aabb(Vec2::from(layout.cell_size)),
+                    Layer::Bunker,
+                ));
+        }
+    }
+}
+
+/// Stops bullets at the cell they run into, damaging it and the cells around
+/// it. Enemies run straight through, wiping out the cells they touch.
+fn bunker_hit(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    bullet_query: Query<&Transform, Without<BunkerCell>>,
+    mut cell_query: Query<(Entity, &Transform, &mut BunkerCell, &mut Sprite)>,
+) {
+    let layout = config.bunkers.layout(config.mode);
+
+    // Cells touched by each bullet, and cells crushed by an enemy.
+    let mut shots: Vec<(Entity, Vec<Entity>)> = Vec::new();
+    let mut crushed: Vec<Entity> = Vec::new();
+
+    for event in events.iter() {
+        if event.other_layer != Layer::Bunker {
+            continue;
+        }
+
+        match event.layer {
+            Layer::Enemy => crushed.push(event.other),
+            _ => match shots.iter_mut().find(|(bullet, _)| *bullet == event.entity) {
+                Some((_, cells)) => cells.push(event.other),
+                None => shots.push((event.entity, vec![event.other])),
+            },
+        }
+    }
+
+    if shots.is_empty() && crushed.is_empty() {
+        return;
+    }
+
+    let mut cells: Vec<(Entity, Vec3, u32)> = cell_query
+        .iter()
+        .map(|(entity, transform, cell, _)| (entity, transform.translation, cell.0))
+        .collect();
+
+    for (bullet, touched) in shots {
+        let bullet_position = match bullet_query.get(bullet) {
+            Ok(transform) => transform.translation,
+            Err(_) => continue,
+        };
+
+        // The cell the bullet went furthest into takes the hit.
+        let hit = cells
+            .iter()
+            .filter(|(cell, _, health)| *health > 0 && touched.contains(cell))
+            .min_by(|(_, a, _), (_, b, _)| {
+                let a = a.distance_squared(bullet_position);
+                let b = b.distance_squared(bullet_position);
+                a.partial_cmp(&b).unwrap()
+            })
+            .map(|(_, position, _)| *position);
+
+        if let Some(center) = hit {
+            for (_, position, health) in cells.iter_mut() {
+                if *health > 0 && position.distance(center) <= layout.blast_radius {
+                    *health -= 1;
+                }
+            }
+        }
+    }
+
+    for (cell, _, health) in cells.iter_mut() {
+        if crushed.contains(cell) {
+            *health = 0;
+        }
+    }
+
+    for (entity, _, health) in cells {
+        let (_, _, mut cell, mut sprite) = cell_query.get_mut(entity).unwrap();
+
+        if health == cell.0 {
+            continue;
+        }
+
+        cell.0 = health;
+
+        if health == 0 {
+            commands.entity(entity).despawn();
+        } else {
+            let strength = health as f32 / layout.cell_health as f32;
+            sprite.color = materials.bunker;
+            sprite.color.set_a(strength);
+        }
+    }
+}
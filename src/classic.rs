@@ -0,0 +1,249 @@
+use crate::{
+    collision::{Collider, Layer, Shape},
+    config::{GameConfig, GameMode},
+    enemies::{spawn_enemy_bullet, Enemy},
+    health::Health,
+    player::{resting_height, Player, PlayerState},
+    rng::GameRng,
+    sim::{Interpolated, SimTime, SIMULATION},
+    waves::WaveManager,
+    GameState, Materials, WinSize,
+};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+/// Gap kept between the grid and the sides of the playfield.
+const SIDE_MARGIN: f32 = 10.0;
+
+/// The classic game mode: a grid of invaders marching side to side and
+/// dropping a row at each edge.
+pub struct ClassicPlugin;
+
+impl Plugin for ClassicPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GridMarch::default())
+            .add_system_set_to_stage(
+                SIMULATION,
+                SystemSet::new()
+                    .with_system(spawn_grid)
+                    .with_system(grid_march)
+                    .with_system(grid_fire)
+                    .with_system(grid_landing),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(reset_grid));
+    }
+}
+
+// -- Components --
+/// Place of an enemy in the marching grid.
+#[derive(Component)]
+pub struct Marching {
+    column: u32,
+    row: u32,
+}
+
+// -- Resources --
+pub struct GridMarch {
+    /// 1 when marching right, -1 when marching left.
+    direction: f32,
+    last_step: u64,
+    /// Invaders in the grid when it spawned.
+    total: u32,
+}
+
+impl Default for GridMarch {
+    fn default() -> Self {
+        Self {
+            direction: 1.0,
+            last_step: 0,
+            total: 0,
+        }
+    }
+}
+
+impl GridMarch {
+    /// Ticks between steps, shrinking as the grid thins out.
+    fn interval(&self, alive: u32, config: &GameConfig, waves: &WaveManager) -> u64 {
+        let classic = &config.classic;
+        let span = (classic.march_interval - classic.min_march_interval) as f32;
+        let left = alive.saturating_sub(1) as f32 / self.total.saturating_sub(1).max(1) as f32;
+        let interval = classic.min_march_interval as f32 + span * left;
+
+        ((interval / waves.wave().speed).round() as u64).max(1)
+    }
+}
+
+fn reset_grid(mut march: ResMut<GridMarch>) {
+    *march = GridMarch::default();
+}
+
+fn spawn_grid(
+    mut commands: Commands,
+    mut waves: ResMut<WaveManager>,
+    mut march: ResMut<GridMarch>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+) {
+    let now = sim_time.ticks();
+
+    if config.mode != GameMode::Classic || waves.pending(now) == 0 {
+        return;
+    }
+
+    let classic = &config.classic;
+    let (spacing_x, spacing_y) = classic.spacing;
+    let left = -spacing_x * (classic.columns - 1) as f32 / 2.0;
+    let top = win_size.h / 2.0 - classic.top;
+
+    for row in 0..classic.rows {
+        let index = classic.row_kind(row, &config.enemies.kinds);
+        let kind = &config.enemies.kinds[index];
+
+        for column in 0..classic.columns {
+            let x = left + spacing_x * column as f32;
+            let y = top - spacing_y * row as f32;
+            let translation = Vec3::new(x, y, 10.0);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::from(classic.size)),
+                        color: kind.color(),
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(Enemy(index))
+                .insert(Health::new(kind.health))
+                .insert(
+                    Collider::new(
+                        Shape::from_config(classic.hitbox, classic.size),
+                        Layer::Enemy,
+                    )
+                    .hits(&[Layer::Bunker]),
+                )
+                .insert(Interpolated::new(translation))
+                .insert(Marching { column, row });
+
+            waves.count_spawn(now);
+        }
+    }
+
+    *march = GridMarch {
+        last_step: now,
+        total: classic.columns * classic.rows,
+        ..Default::default()
+    };
+}
+
+fn grid_march(
+    mut march: ResMut<GridMarch>,
+    sim_time: Res<SimTime>,
+    waves: Res<WaveManager>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    mut query: Query<&mut Transform, With<Marching>>,
+) {
+    let now = sim_time.ticks();
+    let alive = query.iter().count() as u32;
+
+    if alive == 0 || now < march.last_step + march.interval(alive, &config, &waves) {
+        return;
+    }
+
+    march.last_step = now;
+
+    let classic = &config.classic;
+    let half_width = classic.size.0 / 2.0;
+    let edge = win_size.w / 2.0 - SIDE_MARGIN;
+
+    let (min_x, max_x) = query.iter().fold((f32::MAX, f32::MIN), |(min, max), tf| {
+        (min.min(tf.translation.x), max.max(tf.translation.x))
+    });
+
+    let next_min = min_x - half_width + march.direction * classic.step;
+    let next_max = max_x + half_width + march.direction * classic.step;
+
+    if next_min < -edge || next_max > edge {
+        march.direction = -march.direction;
+
+        for mut transform in query.iter_mut() {
+            transform.translation.y -= classic.drop;
+        }
+    } else {
+        for mut transform in query.iter_mut() {
+            transform.translation.x += march.direction * classic.step;
+        }
+    }
+}
+
+/// Shoots from the lowest invader of a random column.
+fn grid_fire(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    sim_time: Res<SimTime>,
+    waves: Res<WaveManager>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    query: Query<(&Transform, &Marching)>,
+) {
+    if sim_time.ticks() % waves.fire_interval(config.classic.fire_interval) != 0 {
+        return;
+    }
+
+    let mut lowest: HashMap<u32, (u32, Vec3)> = HashMap::new();
+    for (transform, slot) in query.iter() {
+        let entry = lowest
+            .entry(slot.column)
+            .or_insert((slot.row, transform.translation));
+
+        if slot.row >= entry.0 {
+            *entry = (slot.row, transform.translation);
+        }
+    }
+
+    // Sorted, as the map's order would make the pick differ between runs.
+    let mut shooters: Vec<(u32, Vec3)> = lowest
+        .into_iter()
+        .map(|(column, (_, translation))| (column, translation))
+        .collect();
+    shooters.sort_by_key(|(column, _)| *column);
+
+    if let Some((_, position)) = shooters.choose(&mut *rng) {
+        let velocity = -Vec2::Y * config.enemies.bullet_speed;
+        spawn_enemy_bullet(&mut commands, &config, &materials, *position, velocity);
+    }
+}
+
+/// Ends the run once the grid comes down to the player's row.
+fn grid_landing(
+    mut commands: Commands,
+    mut player_state: ResMut<PlayerState>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    player_query: Query<Entity, With<Player>>,
+    query: Query<&Transform, With<Marching>>,
+) {
+    let player_top = resting_height(&config, &win_size) + config.player.size.1 / 2.0;
+    let half_height = config.classic.size.1 / 2.0;
+
+    let landed = query
+        .iter()
+        .any(|transform| transform.translation.y - half_height <= player_top);
+
+    if landed && player_state.lives() > 0 {
+        for player in player_query.iter() {
+            commands.entity(player).despawn();
+        }
+
+        player_state.lose_all_at_tick(sim_time.ticks());
+    }
+}
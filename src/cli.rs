@@ -0,0 +1,91 @@
+use crate::config::GameMode;
+
+use std::{env, path::PathBuf, process};
+
+const USAGE: &str = "\
+Usage: rust-invaders [OPTIONS]
+
+Options:
+    --config <FILE>   Load gameplay tuning from FILE [default: assets/config.ron]
+    --mode <MODE>     Game mode, arcade or classic [default: from config]
+    --headless        Run the gameplay without a window and print the result
+    --ticks <N>       Number of ticks to simulate in headless mode [default: 3600]
+    --width <W>       Playfield width in headless mode [default: from config]
+    --height <H>      Playfield height in headless mode [default: from config]
+    --seed <SEED>     Seed for all gameplay randomness [default: time based]
+    --record <FILE>   Save a replay of the run to FILE
+    --replay <FILE>   Play back a replay without a window and verify its score
+    -h, --help        Print this message";
+
+pub struct Options {
+    pub config: Option<PathBuf>,
+    pub mode: Option<GameMode>,
+    pub headless: bool,
+    pub ticks: u64,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            config: None,
+            mode: None,
+            headless: false,
+            ticks: 3600,
+            width: None,
+            height: None,
+            seed: None,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the process arguments, exiting with the usage text on error.
+    pub fn from_args() -> Self {
+        match Self::parse(env::args().skip(1)) {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("{}\n\n{}", err, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => options.config = Some(value(&arg, args.next())?),
+                "--mode" => options.mode = Some(value(&arg, args.next())?),
+                "--headless" => options.headless = true,
+                "--ticks" => options.ticks = value(&arg, args.next())?,
+                "--width" => options.width = Some(value(&arg, args.next())?),
+                "--height" => options.height = Some(value(&arg, args.next())?),
+                "--seed" => options.seed = Some(value(&arg, args.next())?),
+                "--record" => options.record = Some(value(&arg, args.next())?),
+                "--replay" => options.replay = Some(value(&arg, args.next())?),
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for '{}'", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for '{}'", value, flag))
+}
@@ -0,0 +1,274 @@
+use crate::{config::Hitbox, grid::SpatialGrid, player::Invulnerable, sim::SIMULATION, WinSize};
+
+use bevy::prelude::*;
+
+/// Side of a grid cell; a few enemies wide, so most lookups touch one to four cells.
+const CELL_SIZE: f32 = 64.0;
+
+/// Finds what touches what once per tick and reports it as `CollisionEvent`s,
+/// for the gameplay systems to respond to. Also drops whatever left the playfield.
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpatialGrid::new(CELL_SIZE))
+            .add_event::<CollisionEvent>()
+            // Added ahead of the gameplay systems, so contacts are found where
+            // things stood at the end of the last tick, and every response
+            // this tick sees them.
+            .add_system_set_to_stage(
+                SIMULATION,
+                SystemSet::new()
+                    .with_system(rebuild_grid)
+                    .with_system(detect_collisions)
+                    .with_system(despawn_on_hit)
+                    .with_system(despawn_out_of_bounds),
+            );
+    }
+}
+
+/// What a collider is, for others' masks to pick from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Player,
+    PlayerBullet,
+    Enemy,
+    EnemyBullet,
+    Bonus,
+    Bunker,
+    Boss,
+    WeakPoint,
+    Ufo,
+    Laser,
+}
+
+impl Layer {
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Aabb(Vec2),
+    Circle(f32),
+}
+
+impl Shape {
+    /// The configured `hitbox`, or a box of the sprite's `size` if there's none.
+    pub fn from_config(hitbox: Option<Hitbox>, size: (f32, f32)) -> Self {
+        match hitbox {
+            Some(Hitbox::Aabb(size)) => Shape::Aabb(Vec2::from(size)),
+            Some(Hitbox::Circle(radius)) => Shape::Circle(radius),
+            None => Shape::Aabb(Vec2::from(size)),
+        }
+    }
+
+    /// Size of the box around the shape.
+    pub fn bounds(self) -> Vec2 {
+        match self {
+            Shape::Aabb(size) => size,
+            Shape::Circle(radius) => Vec2::splat(radius * 2.0),
+        }
+    }
+
+    fn overlaps(self, position: Vec2, other: Shape, other_position: Vec2) -> bool {
+        match (self, other) {
+            (Shape::Aabb(a), Shape::Aabb(b)) => {
+                let gap = (position - other_position).abs() * 2.0;
+                gap.x < a.x + b.x && gap.y < a.y + b.y
+            }
+            (Shape::Circle(a), Shape::Circle(b)) => position.distance(other_position) < a + b,
+            (Shape::Aabb(size), Shape::Circle(radius)) => {
+                let half = size / 2.0;
+                let closest = other_position.clamp(position - half, position + half);
+                closest.distance(other_position) < radius
+            }
+            (Shape::Circle(_), Shape::Aabb(_)) => other.overlaps(other_position, self, position),
+        }
+    }
+}
+
+// -- Components --
+/// Hitbox of an entity, centered on its translation and independent of its sprite.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Collider {
+    pub shape: Shape,
+    pub layer: Layer,
+    /// Layers this collider reports contacts with.
+    mask: u32,
+}
+
+impl Collider {
+    pub fn new(shape: Shape, layer: Layer) -> Self {
+        Self {
+            shape,
+            layer,
+            mask: 0,
+        }
+    }
+
+    /// Reports contacts with colliders on any of `layers`.
+    pub fn hits(mut self, layers: &[Layer]) -> Self {
+        for layer in layers {
+            self.mask |= layer.bit();
+        }
+        self
+    }
+}
+
+/// Despawns the entity as soon as it touches or is touched by anything.
+#[derive(Component)]
+pub struct DespawnOnHit;
+
+/// Despawns the entity once its center is more than `margin` outside the playfield.
+#[derive(Component)]
+pub struct DespawnOutOfBounds {
+    pub margin: f32,
+}
+
+// -- Events --
+/// `entity` ran into `other`, which is on a layer of its mask.
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionEvent {
+    pub entity: Entity,
+    pub layer: Layer,
+    pub other: Entity,
+    pub other_layer: Layer,
+}
+
+/// Files every collider under the grid cells it covers.
+fn rebuild_grid(mut grid: ResMut<SpatialGrid>, query: Query<(Entity, &Transform, &Collider)>) {
+    grid.clear();
+
+    for (entity, transform, collider) in query.iter() {
+        grid.insert(entity, transform.translation, collider.shape.bounds());
+    }
+}
+
+fn detect_collisions(
+    grid: Res<SpatialGrid>,
+    mut events: EventWriter<CollisionEvent>,
+    query: Query<(Entity, &Transform, &Collider)>,
+    // Nothing lands a hit on an invulnerable player.
+    target_query: Query<(&Transform, &Collider), Without<Invulnerable>>,
+) {
+    for (entity, transform, collider) in query.iter() {
+        if collider.mask == 0 {
+            continue;
+        }
+
+        let position = transform.translation;
+
+        for other in grid.query(position, collider.shape.bounds()) {
+            let (other_tf, other_collider) = match target_query.get(other) {
+                Ok(target) if other != entity => target,
+                _ => continue,
+            };
+
+            if collider.mask & other_collider.layer.bit() != 0
+                && collider.shape.overlaps(
+                    position.truncate(),
+                    other_collider.shape,
+                    other_tf.translation.truncate(),
+                )
+            {
+                events.send(CollisionEvent {
+                    entity,
+                    layer: collider.layer,
+                    other,
+                    other_layer: other_collider.layer,
+                });
+            }
+        }
+    }
+}
+
+fn despawn_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    query: Query<(), With<DespawnOnHit>>,
+) {
+    let mut spent: Vec<Entity> = events
+        .iter()
+        .flat_map(|event| [event.entity, event.other])
+        .filter(|entity| query.get(*entity).is_ok())
+        .collect();
+
+    // Bullets going through several things at once show up more than once.
+    spent.sort();
+    spent.dedup();
+
+    for entity in spent {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn despawn_out_of_bounds(
+    mut commands: Commands,
+    win_size: Res<WinSize>,
+    query: Query<(Entity, &Transform, &DespawnOutOfBounds)>,
+) {
+    let half = Vec2::new(win_size.w, win_size.h) / 2.0;
+
+    for (entity, transform, bounds) in query.iter() {
+        let outside = transform.translation.truncate().abs() - half;
+
+        if outside.max_element() > bounds.margin {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOX: Shape = Shape::Aabb(Vec2::new(10.0, 10.0));
+    const BALL: Shape = Shape::Circle(5.0);
+
+    #[test]
+    fn boxes_overlap_only_past_touching() {
+        assert!(BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(9.9, 0.0)));
+        assert!(!BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(10.0, 0.0)));
+        assert!(!BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(0.0, -25.0)));
+    }
+
+    #[test]
+    fn boxes_meeting_at_a_corner() {
+        assert!(BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(9.9, 9.9)));
+        assert!(!BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(10.0, 10.0)));
+        // Overlapping on one axis isn't enough.
+        assert!(!BOX.overlaps(Vec2::ZERO, BOX, Vec2::new(9.9, 10.1)));
+    }
+
+    #[test]
+    fn circle_and_box_overlap_only_past_touching() {
+        assert!(BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(9.9, 0.0)));
+        assert!(!BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(10.0, 0.0)));
+        assert!(!BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(-20.0, 0.0)));
+    }
+
+    #[test]
+    fn circle_near_a_box_corner() {
+        // 3 right and 4 up from the corner at (5, 5): exactly touching.
+        assert!(!BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(8.0, 9.0)));
+        assert!(BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(8.0, 8.9)));
+        // Inside the box around the circle, but clear of the circle itself.
+        assert!(!BOX.overlaps(Vec2::ZERO, BALL, Vec2::new(9.0, 9.0)));
+    }
+
+    #[test]
+    fn circle_and_box_agree_either_way_round() {
+        for position in [
+            Vec2::new(8.0, 8.9),
+            Vec2::new(9.0, 9.0),
+            Vec2::new(10.0, 0.0),
+        ] {
+            assert_eq!(
+                BOX.overlaps(Vec2::ZERO, BALL, position),
+                BALL.overlaps(position, BOX, Vec2::ZERO)
+            );
+        }
+    }
+}
@@ -0,0 +1,1959 @@
+use crate::powerups::PowerUp;
+
+use bevy::prelude::{Color, Vec2};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+/// Default location of the tuning file, relative to the working directory.
+pub const CONFIG_FILE: &str = "assets/config.ron";
+
+// -- Resources --
+/// Gameplay tuning, loaded from a RON file. Missing keys keep their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    /// Seed for all gameplay randomness; a fresh one is picked per run when unset.
+    pub seed: Option<u64>,
+    /// Window size, also used as the playfield in headless mode.
+    pub width: f32,
+    pub height: f32,
+    /// Seconds per simulation tick.
+    pub time_step: f32,
+    pub mode: GameMode,
+    pub player: PlayerConfig,
+    /// Weapons the player may carry, picked by name in `player.loadout`.
+    pub weapons: Vec<WeaponDef>,
+    pub enemies: EnemyConfig,
+    pub waves: WaveConfig,
+    pub bosses: BossConfig,
+    pub classic: ClassicConfig,
+    pub bunkers: BunkerConfig,
+    pub ufo: UfoConfig,
+    pub powerups: PowerUpConfig,
+}
+
+/// Collision shape used in place of a sprite's own rectangle.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Hitbox {
+    Aabb((f32, f32)),
+    Circle(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Enemies swoop in on their own paths, wave after wave, with bosses.
+    Arcade,
+    /// The marching grid of the original.
+    Classic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayerConfig {
+    pub speed: f32,
+    pub size: (f32, f32),
+    /// What enemy fire has to touch, the sprite when unset.
+    pub hitbox: Option<Hitbox>,
+    /// Damage taken before losing a life.
+    pub health: u32,
+    pub lives: u32,
+    /// Scores at which an extra life is awarded, in ascending order.
+    pub extra_life_at: Vec<u32>,
+    /// Ticks between losing a life and respawning.
+    pub respawn_delay: u64,
+    /// Ticks of invulnerability after a respawn.
+    pub invulnerability: u64,
+    pub movement: MovementConfig,
+    pub loadout: LoadoutConfig,
+}
+
+/// What the player starts each life with, separately for each game mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadoutConfig {
+    pub arcade: Loadout,
+    pub classic: Loadout,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Loadout {
+    /// Name of one of the `weapons`.
+    pub weapon: String,
+    /// Most player bullets on screen at once; unlimited when unset.
+    pub max_bullets: Option<u32>,
+}
+
+/// A player weapon, getting better with each level picked up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeaponDef {
+    pub name: String,
+    pub bullet_speed: f32,
+    pub bullet_size: (f32, f32),
+    pub bullet_hitbox: Option<Hitbox>,
+    /// From the first level up; at least one.
+    pub levels: Vec<WeaponLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeaponLevel {
+    pub pattern: ShotPattern,
+    /// Ticks between shots.
+    pub cooldown: u64,
+    /// Keeps firing while held, rather than once per press.
+    pub auto_fire: bool,
+    /// Damage per bullet.
+    pub damage: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ShotPattern {
+    /// One shot straight up.
+    Single,
+    /// Two shots side by side, `gap` apart.
+    Twin { gap: f32 },
+    /// `count` shots fanned over `spread` radians.
+    Spread { count: u32, spread: f32 },
+    /// A beam going through up to `pierce` targets before it is spent.
+    Laser { pierce: u32 },
+    /// `count` missiles fanned out, each turning toward the nearest target
+    /// at up to `turn_rate` radians per second.
+    Homing { count: u32, turn_rate: f32 },
+}
+
+/// How the player ship handles, separately for each game mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MovementConfig {
+    pub arcade: Movement,
+    pub classic: Movement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Movement {
+    /// Speed gained per second while moving; full speed at once when unset.
+    pub acceleration: Option<f32>,
+    /// Speed lost per second once let go of; stops at once when unset.
+    pub deceleration: Option<f32>,
+    /// Height above the bottom of the playfield the ship may move up to; 0
+    /// keeps it on its starting line.
+    pub band: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemyConfig {
+    pub formation_size: u32,
+    pub bullet_speed: f32,
+    pub bullet_size: (f32, f32),
+    pub bullet_hitbox: Option<Hitbox>,
+    pub bullet_damage: u32,
+    /// Damage an enemy deals by running into the player.
+    pub contact_damage: u32,
+    /// Fraction an enemy's time between volleys varies by, either way, so
+    /// enemies don't all shoot in step.
+    pub fire_jitter: f32,
+    /// Volleys enemy kinds may fire, by name.
+    pub patterns: Vec<FirePatternDef>,
+    /// Ways enemy kinds may move, by name.
+    pub paths: Vec<PathDef>,
+    pub kinds: Vec<EnemyKind>,
+}
+
+/// One type of enemy, e.g. a grunt or an armored tank.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnemyKind {
+    pub name: String,
+    /// Hits it takes to destroy.
+    pub health: u32,
+    pub size: (f32, f32),
+    pub hitbox: Option<Hitbox>,
+    pub color: (f32, f32, f32),
+    pub speed: f32,
+    /// Points per kill.
+    pub score: u32,
+    /// Name of one of the `paths`.
+    pub path: String,
+    /// Name of one of the `patterns`; holds fire when unset.
+    pub fire: Option<String>,
+    /// Ticks between volleys, on average.
+    pub fire_interval: u64,
+    pub drops: DropTable,
+    /// First wave the kind shows up in.
+    pub from_wave: u32,
+    /// How often the kind is picked relative to the others in a wave.
+    pub weight: u32,
+}
+
+/// How an enemy moves, as steps taken one after the other. Points are
+/// fractions of the half playfield, from (-1, -1) at the bottom left to
+/// (1, 1) at the top right.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathDef {
+    pub name: String,
+    /// Starts over from the first once the last step ends.
+    pub steps: Vec<PathStep>,
+    /// Flipped left to right for half the enemies, at random.
+    pub mirror: bool,
+    /// Shifts each enemy's path by up to this much either way, at random.
+    pub scatter: (f32, f32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathStep {
+    pub movement: MovementPath,
+    /// Ticks until the next step; goes on for good when unset.
+    pub duration: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MovementPath {
+    /// Loops around an ellipse placed at random, along with the rest of its formation.
+    Ellipse,
+    /// Swoops down at the player and comes around from the top again.
+    Dive,
+    /// Catmull-Rom spline through all of `points`, over the step's duration.
+    Spline { points: Vec<(f32, f32)> },
+    /// Bezier curve from the first of `points` to the last, pulled toward the
+    /// ones between, over the step's duration.
+    Bezier { points: Vec<(f32, f32)> },
+    /// Heads down, weaving `amplitude` either side every `period` ticks, and
+    /// comes around from the top again.
+    Sine { amplitude: f32, period: u64 },
+    /// Stays where the last step left off, swaying `sway` either side every
+    /// `period` ticks.
+    Hover { sway: f32, period: u64 },
+}
+
+/// A volley made of shots fired together, possibly several times in a row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FirePatternDef {
+    pub name: String,
+    pub shots: Vec<Shot>,
+    /// Volleys fired back to back each time the enemy shoots.
+    pub burst: u32,
+    /// Ticks between the volleys of a burst.
+    pub burst_gap: u64,
+    /// Multiplier on `enemies.bullet_speed`.
+    pub speed: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Shot {
+    /// One shot straight down, `offset` to the side of the enemy's center.
+    Straight { offset: f32 },
+    /// `count` shots fanned over `spread` radians, centered on the player.
+    Aimed { count: u32, spread: f32 },
+    /// `count` shots fanned over `spread` radians, centered straight down.
+    Fan { count: u32, spread: f32 },
+    /// `count` shots evenly spaced all around.
+    Ring { count: u32 },
+    /// A ring turned a further `turn` radians with every volley.
+    Spiral { count: u32, turn: f32 },
+}
+
+/// What a kill may leave behind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DropTable {
+    /// Chance of a kill dropping a bonus.
+    pub chance: f64,
+    /// Relative odds of each power-up; any power-up is equally likely when empty.
+    pub weights: Vec<(PowerUp, u32)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaveConfig {
+    /// Ticks of calm before each wave, while its banner is up.
+    pub break_ticks: u64,
+    /// Hand-made waves, played in order before the generated ones.
+    pub scripted: Vec<WaveDef>,
+    /// How each generated wave past the scripted ones steps up from the last.
+    pub ramp: WaveRamp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaveDef {
+    /// Enemies spawned over the course of the wave.
+    pub enemies: u32,
+    /// Most enemies on screen at once.
+    pub max_active: u32,
+    /// Ticks between spawns.
+    pub spawn_interval: u64,
+    /// Multiplier on the speed of every kind.
+    pub speed: f32,
+    /// Multiplier on the fire rate of every kind.
+    pub fire_rate: f32,
+    /// Kinds that may spawn, by name. When empty, any kind whose `from_wave` has come.
+    pub kinds: Vec<String>,
+    /// Points for clearing the wave.
+    pub clear_bonus: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WaveRamp {
+    pub enemies: u32,
+    /// Extra on-screen enemies per wave; fractions add up over waves.
+    pub max_active: f32,
+    pub max_active_cap: u32,
+    pub speed: f32,
+    pub speed_cap: f32,
+    pub fire_rate: f32,
+    pub fire_rate_cap: f32,
+    pub clear_bonus: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BossConfig {
+    /// A boss takes the place of every Nth wave; 0 turns bosses off.
+    pub every: u32,
+    /// Bosses in order of appearance, starting over after the last.
+    pub roster: Vec<BossDef>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BossDef {
+    pub name: String,
+    pub health: u32,
+    pub size: (f32, f32),
+    pub hitbox: Option<Hitbox>,
+    pub color: (f32, f32, f32),
+    /// Top speed while moving between points of its path.
+    pub speed: f32,
+    pub score: u32,
+    /// Height it hovers at, as a fraction of the way from the center to the top.
+    pub hover_at: f32,
+    pub weak_points: Vec<WeakPointDef>,
+    /// Played in order as its health runs down.
+    pub phases: Vec<BossPhase>,
+}
+
+/// A spot on the boss that takes extra damage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeakPointDef {
+    /// Position relative to the center of the boss.
+    pub offset: (f32, f32),
+    pub size: (f32, f32),
+    /// What damage from a hit here is multiplied by before the boss takes it.
+    pub multiplier: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BossPhase {
+    /// Fraction of health at which the phase ends and the next one begins.
+    pub until: f32,
+    pub movement: BossMovement,
+    pub attacks: Vec<BossAttack>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BossMovement {
+    /// Holds still over the center.
+    Hover,
+    /// Swings from side to side, `period` ticks per round trip.
+    Sweep { width: f32, period: u64 },
+    /// Traces a figure eight, `period` ticks per loop.
+    FigureEight {
+        width: f32,
+        height: f32,
+        period: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossAttack {
+    pub pattern: AttackPattern,
+    /// Ticks between uses.
+    pub interval: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AttackPattern {
+    /// `count` shots fanned over `spread` radians, centered on the player.
+    Aimed { count: u32, spread: f32 },
+    /// `count` shots evenly spaced all around.
+    Radial { count: u32 },
+    /// A beam straight down that warms up, then sweeps toward the player.
+    Laser {
+        width: f32,
+        /// Ticks of harmless warning before the beam is live.
+        warmup: u64,
+        /// Ticks the live beam lasts.
+        duration: u64,
+        /// Sideways speed of the live beam.
+        sweep: f32,
+        /// Damage to the player caught in the live beam.
+        damage: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassicConfig {
+    pub columns: u32,
+    pub rows: u32,
+    /// Enemy kind of each row, by name from the top; the last one fills any rows left.
+    pub row_kinds: Vec<String>,
+    /// Size of every invader, whatever its kind.
+    pub size: (f32, f32),
+    pub hitbox: Option<Hitbox>,
+    /// Distance between the centers of neighbouring invaders.
+    pub spacing: (f32, f32),
+    /// Distance from the top of the playfield to the top row.
+    pub top: f32,
+    /// Sideways distance of each march step.
+    pub step: f32,
+    /// How far the grid drops on reaching an edge.
+    pub drop: f32,
+    /// Ticks between steps with the full grid, down to `min_march_interval`
+    /// for the last invader standing.
+    pub march_interval: u64,
+    pub min_march_interval: u64,
+    /// Ticks between shots from the bottom of a random column.
+    pub fire_interval: u64,
+}
+
+/// Bunkers shielding the player, laid out separately for each game mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BunkerConfig {
+    pub arcade: BunkerLayout,
+    pub classic: BunkerLayout,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BunkerLayout {
+    /// Bunkers spread evenly across the playfield; 0 for none.
+    pub count: u32,
+    /// Rows of cells from the top, `#` being a cell and anything else a gap.
+    pub shape: Vec<String>,
+    pub cell_size: (f32, f32),
+    /// Hits each cell takes before it breaks.
+    pub cell_health: u32,
+    /// Cells within this distance of the one hit are damaged along with it.
+    pub blast_radius: f32,
+    /// Distance from the bottom of the playfield to the center of the bunkers.
+    pub height: f32,
+}
+
+/// The mystery ship crossing the top of the playfield now and then.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UfoConfig {
+    /// Ticks from one pass to the next, counted while none is on screen.
+    pub interval: u64,
+    pub speed: f32,
+    pub size: (f32, f32),
+    pub hitbox: Option<Hitbox>,
+    pub color: (f32, f32, f32),
+    /// Distance from the top of the playfield to the UFO's path.
+    pub top: f32,
+    /// Scores it may be worth, one picked at random when it is shot down.
+    pub points: Vec<u32>,
+    pub drops: DropTable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PowerUpConfig {
+    pub size: (f32, f32),
+    pub hitbox: Option<Hitbox>,
+    pub fall_speed: f32,
+    /// Ticks each effect lasts.
+    pub duration: u64,
+    pub speed_boost: f32,
+    pub score_multiplier: u32,
+    /// Angle of the extra shots either side while spread shot is on, in radians.
+    pub spread: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: 480.0,
+            height: 640.0,
+            time_step: 1.0 / 60.0,
+            mode: GameMode::Arcade,
+            player: PlayerConfig::default(),
+            weapons: vec![
+                WeaponDef::default(),
+                WeaponDef {
+                    name: "laser".to_owned(),
+                    bullet_speed: 480.0,
+                    bullet_size: (3.0, 24.0),
+                    levels: vec![
+                        WeaponLevel {
+                            pattern: ShotPattern::Laser { pierce: 2 },
+                            cooldown: 30,
+                            auto_fire: true,
+                            damage: 1,
+                        },
+                        WeaponLevel {
+                            pattern: ShotPattern::Laser { pierce: 4 },
+                            cooldown: 24,
+                            auto_fire: true,
+                            damage: 1,
+                        },
+                        WeaponLevel {
+                            pattern: ShotPattern::Laser { pierce: 8 },
+                            cooldown: 18,
+                            auto_fire: true,
+                            damage: 2,
+                        },
+                    ],
+                    ..Default::default()
+                },
+                WeaponDef {
+                    name: "missiles".to_owned(),
+                    bullet_speed: 180.0,
+                    bullet_size: (4.0, 8.0),
+                    levels: vec![
+                        WeaponLevel {
+                            pattern: ShotPattern::Homing {
+                                count: 1,
+                                turn_rate: 3.0,
+                            },
+                            cooldown: 20,
+                            auto_fire: true,
+                            damage: 1,
+                        },
+                        WeaponLevel {
+                            pattern: ShotPattern::Homing {
+                                count: 2,
+                                turn_rate: 3.0,
+                            },
+                            cooldown: 20,
+                            auto_fire: true,
+                            damage: 1,
+                        },
+                        WeaponLevel {
+                            pattern: ShotPattern::Homing {
+                                count: 4,
+                                turn_rate: 4.0,
+                            },
+                            cooldown: 18,
+                            auto_fire: true,
+                            damage: 1,
+                        },
+                    ],
+                    ..Default::default()
+                },
+                WeaponDef {
+                    name: "cannon".to_owned(),
+                    levels: vec![WeaponLevel {
+                        pattern: ShotPattern::Single,
+                        cooldown: 0,
+                        auto_fire: false,
+                        damage: 1,
+                    }],
+                    ..Default::default()
+                },
+            ],
+            enemies: EnemyConfig::default(),
+            waves: WaveConfig::default(),
+            bosses: BossConfig::default(),
+            classic: ClassicConfig::default(),
+            bunkers: BunkerConfig::default(),
+            ufo: UfoConfig::default(),
+            powerups: PowerUpConfig::default(),
+        }
+    }
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            speed: 200.0,
+            size: (10.0, 10.0),
+            hitbox: None,
+            health: 1,
+            lives: 3,
+            extra_life_at: vec![10_000, 25_000, 50_000],
+            respawn_delay: 60,
+            invulnerability: 120,
+            movement: MovementConfig::default(),
+            loadout: LoadoutConfig::default(),
+        }
+    }
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        Self {
+            formation_size: 2,
+            bullet_speed: 200.0,
+            bullet_size: (2.0, 16.0),
+            bullet_hitbox: None,
+            bullet_damage: 1,
+            contact_damage: 1,
+            fire_jitter: 0.3,
+            patterns: vec![
+                FirePatternDef::default(),
+                FirePatternDef {
+                    name: "double".to_owned(),
+                    shots: vec![
+                        Shot::Straight { offset: -7.0 },
+                        Shot::Straight { offset: 7.0 },
+                    ],
+                    ..Default::default()
+                },
+                FirePatternDef {
+                    name: "aimed".to_owned(),
+                    shots: vec![Shot::Aimed {
+                        count: 1,
+                        spread: 0.0,
+                    }],
+                    ..Default::default()
+                },
+                FirePatternDef {
+                    name: "burst".to_owned(),
+                    shots: vec![Shot::Aimed {
+                        count: 1,
+                        spread: 0.0,
+                    }],
+                    burst: 3,
+                    burst_gap: 8,
+                    speed: 1.25,
+                },
+                FirePatternDef {
+                    name: "fan".to_owned(),
+                    shots: vec![Shot::Fan {
+                        count: 3,
+                        spread: 0.6,
+                    }],
+                    ..Default::default()
+                },
+                FirePatternDef {
+                    name: "ring".to_owned(),
+                    shots: vec![Shot::Ring { count: 8 }],
+                    speed: 0.75,
+                    ..Default::default()
+                },
+                FirePatternDef {
+                    name: "spiral".to_owned(),
+                    shots: vec![Shot::Spiral {
+                        count: 4,
+                        turn: 0.3,
+                    }],
+                    burst: 6,
+                    burst_gap: 6,
+                    speed: 0.75,
+                },
+            ],
+            paths: vec![
+                PathDef::default(),
+                PathDef {
+                    name: "dive".to_owned(),
+                    steps: vec![PathStep {
+                        movement: MovementPath::Dive,
+                        duration: None,
+                    }],
+                    ..Default::default()
+                },
+                PathDef {
+                    name: "swoop".to_owned(),
+                    steps: vec![
+                        PathStep {
+                            movement: MovementPath::Spline {
+                                points: vec![
+                                    (1.1, 0.6),
+                                    (0.5, 0.1),
+                                    (0.0, -0.2),
+                                    (-0.4, 0.1),
+                                    (-0.1, 0.5),
+                                ],
+                            },
+                            duration: Some(180),
+                        },
+                        PathStep {
+                            movement: MovementPath::Hover {
+                                sway: 16.0,
+                                period: 120,
+                            },
+                            duration: Some(360),
+                        },
+                        PathStep {
+                            movement: MovementPath::Dive,
+                            duration: None,
+                        },
+                    ],
+                    mirror: true,
+                    scatter: (0.3, 0.1),
+                },
+                PathDef {
+                    name: "loop".to_owned(),
+                    steps: vec![
+                        PathStep {
+                            movement: MovementPath::Bezier {
+                                points: vec![(-1.1, 0.8), (0.6, 0.9), (0.6, -0.4), (-0.2, 0.3)],
+                            },
+                            duration: Some(180),
+                        },
+                        PathStep {
+                            movement: MovementPath::Ellipse,
+                            duration: None,
+                        },
+                    ],
+                    mirror: true,
+                    scatter: (0.1, 0.1),
+                },
+                PathDef {
+                    name: "weave".to_owned(),
+                    steps: vec![PathStep {
+                        movement: MovementPath::Sine {
+                            amplitude: 60.0,
+                            period: 120,
+                        },
+                        duration: None,
+                    }],
+                    ..Default::default()
+                },
+            ],
+            kinds: vec![
+                EnemyKind::default(),
+                EnemyKind {
+                    name: "shooter".to_owned(),
+                    size: (28.0, 14.0),
+                    color: (0.9, 0.6, 0.2),
+                    speed: 150.0,
+                    score: 150,
+                    path: "swoop".to_owned(),
+                    fire: Some("burst".to_owned()),
+                    fire_interval: 60,
+                    from_wave: 2,
+                    weight: 3,
+                    ..Default::default()
+                },
+                EnemyKind {
+                    name: "diver".to_owned(),
+                    size: (20.0, 16.0),
+                    color: (0.7, 0.3, 0.9),
+                    speed: 320.0,
+                    score: 200,
+                    path: "dive".to_owned(),
+                    fire: None,
+                    drops: DropTable {
+                        chance: 0.1,
+                        ..Default::default()
+                    },
+                    from_wave: 3,
+                    weight: 2,
+                    ..Default::default()
+                },
+                EnemyKind {
+                    name: "tank".to_owned(),
+                    health: 4,
+                    size: (44.0, 18.0),
+                    color: (0.5, 0.55, 0.6),
+                    speed: 120.0,
+                    score: 400,
+                    fire: Some("fan".to_owned()),
+                    fire_interval: 90,
+                    drops: DropTable {
+                        chance: 0.6,
+                        weights: vec![(PowerUp::Shield, 2), (PowerUp::SpreadShot, 1)],
+                    },
+                    from_wave: 4,
+                    weight: 1,
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+}
+
+/// The plain grunt.
+impl Default for EnemyKind {
+    fn default() -> Self {
+        Self {
+            name: "grunt".to_owned(),
+            health: 1,
+            size: (36.0, 12.0),
+            hitbox: None,
+            color: (0.8, 0.2, 0.26),
+            speed: 200.0,
+            score: 100,
+            path: "formation".to_owned(),
+            fire: Some("single".to_owned()),
+            fire_interval: 51,
+            drops: DropTable::default(),
+            from_wave: 1,
+            weight: 6,
+        }
+    }
+}
+
+/// The original loop around an ellipse.
+impl Default for PathDef {
+    fn default() -> Self {
+        Self {
+            name: "formation".to_owned(),
+            steps: vec![PathStep {
+                movement: MovementPath::Ellipse,
+                duration: None,
+            }],
+            mirror: false,
+            scatter: (0.0, 0.0),
+        }
+    }
+}
+
+/// One shot straight down.
+impl Default for FirePatternDef {
+    fn default() -> Self {
+        Self {
+            name: "single".to_owned(),
+            shots: vec![Shot::Straight { offset: 0.0 }],
+            burst: 1,
+            burst_gap: 0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Default for DropTable {
+    fn default() -> Self {
+        Self {
+            chance: 0.2,
+            weights: Vec::new(),
+        }
+    }
+}
+
+impl Default for WaveConfig {
+    fn default() -> Self {
+        Self {
+            break_ticks: 120,
+            scripted: vec![
+                WaveDef::default(),
+                WaveDef {
+                    enemies: 8,
+                    clear_bonus: 750,
+                    ..Default::default()
+                },
+                WaveDef {
+                    enemies: 10,
+                    max_active: 4,
+                    spawn_interval: 50,
+                    speed: 1.1,
+                    fire_rate: 1.1,
+                    clear_bonus: 1000,
+                    ..Default::default()
+                },
+                WaveDef {
+                    enemies: 12,
+                    max_active: 4,
+                    spawn_interval: 50,
+                    speed: 1.15,
+                    fire_rate: 1.2,
+                    clear_bonus: 1250,
+                    ..Default::default()
+                },
+            ],
+            ramp: WaveRamp::default(),
+        }
+    }
+}
+
+impl Default for WaveDef {
+    fn default() -> Self {
+        Self {
+            enemies: 6,
+            max_active: 3,
+            spawn_interval: 60,
+            speed: 1.0,
+            fire_rate: 1.0,
+            kinds: Vec::new(),
+            clear_bonus: 500,
+        }
+    }
+}
+
+impl Default for WaveRamp {
+    fn default() -> Self {
+        Self {
+            enemies: 2,
+            max_active: 0.25,
+            max_active_cap: 8,
+            speed: 0.05,
+            speed_cap: 2.0,
+            fire_rate: 0.1,
+            fire_rate_cap: 3.0,
+            clear_bonus: 250,
+        }
+    }
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        Self {
+            every: 5,
+            roster: vec![BossDef::default()],
+        }
+    }
+}
+
+/// The mothership.
+impl Default for BossDef {
+    fn default() -> Self {
+        Self {
+            name: "mothership".to_owned(),
+            health: 60,
+            size: (96.0, 32.0),
+            hitbox: None,
+            color: (0.55, 0.15, 0.2),
+            speed: 120.0,
+            score: 5000,
+            hover_at: 0.6,
+            weak_points: vec![
+                WeakPointDef {
+                    offset: (-30.0, -12.0),
+                    ..Default::default()
+                },
+                WeakPointDef {
+                    offset: (30.0, -12.0),
+                    ..Default::default()
+                },
+            ],
+            phases: vec![
+                BossPhase {
+                    until: 0.66,
+                    movement: BossMovement::Sweep {
+                        width: 300.0,
+                        period: 240,
+                    },
+                    attacks: vec![BossAttack {
+                        pattern: AttackPattern::Aimed {
+                            count: 3,
+                            spread: 0.4,
+                        },
+                        interval: 60,
+                    }],
+                },
+                BossPhase {
+                    until: 0.33,
+                    movement: BossMovement::FigureEight {
+                        width: 320.0,
+                        height: 80.0,
+                        period: 360,
+                    },
+                    attacks: vec![
+                        BossAttack {
+                            pattern: AttackPattern::Radial { count: 12 },
+                            interval: 90,
+                        },
+                        BossAttack {
+                            pattern: AttackPattern::Aimed {
+                                count: 1,
+                                spread: 0.0,
+                            },
+                            interval: 40,
+                        },
+                    ],
+                },
+                BossPhase {
+                    until: 0.0,
+                    movement: BossMovement::Hover,
+                    attacks: vec![
+                        BossAttack {
+                            pattern: AttackPattern::Laser {
+                                width: 16.0,
+                                warmup: 45,
+                                duration: 120,
+                                sweep: 80.0,
+                                damage: 1,
+                            },
+                            interval: 240,
+                        },
+                        BossAttack {
+                            pattern: AttackPattern::Radial { count: 16 },
+                            interval: 70,
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+}
+
+impl Default for WeakPointDef {
+    fn default() -> Self {
+        Self {
+            offset: (0.0, 0.0),
+            size: (12.0, 12.0),
+            multiplier: 3,
+        }
+    }
+}
+
+impl Default for BossPhase {
+    fn default() -> Self {
+        Self {
+            until: 0.0,
+            movement: BossMovement::Hover,
+            attacks: Vec::new(),
+        }
+    }
+}
+
+impl Default for ClassicConfig {
+    fn default() -> Self {
+        Self {
+            columns: 11,
+            rows: 5,
+            row_kinds: ["diver", "shooter", "shooter", "grunt", "grunt"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            size: (24.0, 16.0),
+            hitbox: None,
+            spacing: (36.0, 30.0),
+            top: 90.0,
+            step: 8.0,
+            drop: 16.0,
+            march_interval: 40,
+            min_march_interval: 2,
+            fire_interval: 50,
+        }
+    }
+}
+
+impl Default for LoadoutConfig {
+    fn default() -> Self {
+        Self {
+            arcade: Loadout::default(),
+            classic: Loadout {
+                weapon: "cannon".to_owned(),
+                max_bullets: Some(1),
+            },
+        }
+    }
+}
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Self {
+            weapon: "blaster".to_owned(),
+            max_bullets: None,
+        }
+    }
+}
+
+impl Default for WeaponDef {
+    fn default() -> Self {
+        Self {
+            name: "blaster".to_owned(),
+            bullet_speed: 200.0,
+            bullet_size: (2.0, 4.0),
+            bullet_hitbox: None,
+            levels: vec![
+                WeaponLevel {
+                    pattern: ShotPattern::Single,
+                    cooldown: 10,
+                    auto_fire: false,
+                    damage: 1,
+                },
+                WeaponLevel {
+                    pattern: ShotPattern::Single,
+                    cooldown: 10,
+                    auto_fire: true,
+                    damage: 1,
+                },
+                WeaponLevel {
+                    pattern: ShotPattern::Twin { gap: 8.0 },
+                    cooldown: 10,
+                    auto_fire: true,
+                    damage: 1,
+                },
+                WeaponLevel {
+                    pattern: ShotPattern::Spread {
+                        count: 3,
+                        spread: 0.5,
+                    },
+                    cooldown: 8,
+                    auto_fire: true,
+                    damage: 1,
+                },
+            ],
+        }
+    }
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            arcade: Movement {
+                acceleration: Some(1600.0),
+                deceleration: Some(2400.0),
+                band: 120.0,
+            },
+            classic: Movement::default(),
+        }
+    }
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            acceleration: None,
+            deceleration: None,
+            band: 0.0,
+        }
+    }
+}
+
+impl Default for BunkerConfig {
+    fn default() -> Self {
+        Self {
+            arcade: BunkerLayout {
+                count: 0,
+                ..Default::default()
+            },
+            classic: BunkerLayout::default(),
+        }
+    }
+}
+
+impl Default for BunkerLayout {
+    fn default() -> Self {
+        Self {
+            count: 4,
+            shape: [
+                "   ######   ",
+                "  ########  ",
+                " ########## ",
+                "############",
+                "############",
+                "###      ###",
+                "##        ##",
+            ]
+            .iter()
+            .map(|row| row.to_string())
+            .collect(),
+            cell_size: (4.0, 4.0),
+            cell_health: 1,
+            blast_radius: 4.0,
+            height: 80.0,
+        }
+    }
+}
+
+impl Default for UfoConfig {
+    fn default() -> Self {
+        Self {
+            interval: 1500,
+            speed: 120.0,
+            size: (32.0, 14.0),
+            hitbox: None,
+            color: (0.9, 0.2, 0.25),
+            top: 50.0,
+            points: vec![50, 100, 150, 300],
+            // Always carries a power-up.
+            drops: DropTable {
+                chance: 1.0,
+                weights: Vec::new(),
+            },
+        }
+    }
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Arcade, GameMode::Classic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Arcade => "Arcade",
+            GameMode::Classic => "Classic",
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl ClassicConfig {
+    /// Index into `kinds` of the enemy kind filling `row`, counting from the top.
+    pub fn row_kind(&self, row: u32, kinds: &[EnemyKind]) -> usize {
+        let name = &self.row_kinds[(row as usize).min(self.row_kinds.len() - 1)];
+
+        kinds
+            .iter()
+            .position(|kind| &kind.name == name)
+            .expect("validated config names existing kinds")
+    }
+}
+
+impl LoadoutConfig {
+    pub fn get(&self, mode: GameMode) -> &Loadout {
+        match mode {
+            GameMode::Arcade => &self.arcade,
+            GameMode::Classic => &self.classic,
+        }
+    }
+}
+
+impl MovementConfig {
+    pub fn get(&self, mode: GameMode) -> &Movement {
+        match mode {
+            GameMode::Arcade => &self.arcade,
+            GameMode::Classic => &self.classic,
+        }
+    }
+}
+
+impl BunkerConfig {
+    pub fn layout(&self, mode: GameMode) -> &BunkerLayout {
+        match mode {
+            GameMode::Arcade => &self.arcade,
+            GameMode::Classic => &self.classic,
+        }
+    }
+}
+
+impl BunkerLayout {
+    /// Offsets of the cells from the center of a bunker.
+    pub fn cells(&self) -> Vec<Vec2> {
+        let (cell_w, cell_h) = self.cell_size;
+        let rows = self.shape.len() as f32;
+        let columns = self.shape.iter().map(|row| row.len()).max().unwrap_or(0) as f32;
+
+        let mut cells = Vec::new();
+        for (row, line) in self.shape.iter().enumerate() {
+            for (column, cell) in line.chars().enumerate() {
+                if cell == '#' {
+                    let x = (column as f32 - (columns - 1.0) / 2.0) * cell_w;
+                    let y = ((rows - 1.0) / 2.0 - row as f32) * cell_h;
+                    cells.push(Vec2::new(x, y));
+                }
+            }
+        }
+
+        cells
+    }
+}
+
+impl UfoConfig {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = self.color;
+        Color::rgb(r, g, b)
+    }
+}
+
+impl BossConfig {
+    /// The boss taking the place of wave `number`, if any.
+    pub fn for_wave(&self, number: u32) -> Option<usize> {
+        if self.every == 0 || self.roster.is_empty() || number % self.every != 0 {
+            return None;
+        }
+
+        Some((number / self.every - 1) as usize % self.roster.len())
+    }
+}
+
+impl BossDef {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = self.color;
+        Color::rgb(r, g, b)
+    }
+
+    /// Index of the phase for the given fraction of health left.
+    pub fn phase(&self, health: f32) -> usize {
+        self.phases
+            .iter()
+            .position(|phase| health > phase.until)
+            .unwrap_or(self.phases.len() - 1)
+    }
+}
+
+impl WaveConfig {
+    /// The definition of wave `number`, counting from 1: a scripted wave while
+    /// there are any left, generated from the last one after that.
+    pub fn wave(&self, number: u32) -> WaveDef {
+        let scripted = self.scripted.len() as u32;
+
+        if let Some(wave) = self.scripted.get(number.saturating_sub(1) as usize) {
+            return wave.clone();
+        }
+
+        let base = self.scripted.last().cloned().unwrap_or_default();
+        let ramp = &self.ramp;
+        let steps = number - scripted.max(1);
+        let extra_active = (ramp.max_active * steps as f32) as u32;
+
+        WaveDef {
+            enemies: base.enemies + ramp.enemies * steps,
+            max_active: (base.max_active + extra_active)
+                .min(ramp.max_active_cap.max(base.max_active)),
+            speed: (base.speed + ramp.speed * steps as f32).min(ramp.speed_cap.max(base.speed)),
+            fire_rate: (base.fire_rate + ramp.fire_rate * steps as f32)
+                .min(ramp.fire_rate_cap.max(base.fire_rate)),
+            kinds: Vec::new(),
+            clear_bonus: base.clear_bonus + ramp.clear_bonus * steps,
+            ..base
+        }
+    }
+}
+
+impl EnemyKind {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = self.color;
+        Color::rgb(r, g, b)
+    }
+}
+
+impl EnemyConfig {
+    /// Picks the kind of the next enemy, by weight among those allowed in wave
+    /// `number`, or among the `only` names when given.
+    pub fn pick_kind(&self, number: u32, only: &[String], rng: &mut impl Rng) -> usize {
+        let eligible: Vec<usize> = (0..self.kinds.len())
+            .filter(|&i| {
+                let kind = &self.kinds[i];
+
+                if only.is_empty() {
+                    kind.from_wave <= number
+                } else {
+                    only.contains(&kind.name)
+                }
+            })
+            .collect();
+
+        *eligible
+            .choose_weighted(rng, |&i| self.kinds[i].weight)
+            .expect("validated config has a kind for every wave")
+    }
+
+    /// Index into `paths` of how a kind moves.
+    pub fn path(&self, kind: &EnemyKind) -> Option<usize> {
+        self.paths.iter().position(|path| path.name == kind.path)
+    }
+
+    /// Index into `patterns` of what a kind fires, if anything.
+    pub fn fire_pattern(&self, kind: &EnemyKind) -> Option<usize> {
+        let name = kind.fire.as_ref()?;
+
+        self.patterns
+            .iter()
+            .position(|pattern| &pattern.name == name)
+    }
+}
+
+impl DropTable {
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<PowerUp> {
+        if !rng.gen_bool(self.chance) {
+            return None;
+        }
+
+        if self.weights.is_empty() {
+            return Some(PowerUp::random(rng));
+        }
+
+        self.weights
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .ok()
+            .map(|(kind, _)| *kind)
+    }
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        Self {
+            size: (8.0, 8.0),
+            hitbox: None,
+            fall_speed: 100.0,
+            duration: 480,
+            speed_boost: 1.5,
+            score_multiplier: 2,
+            spread: 0.25,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(String),
+    Invalid { field: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "could not read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "could not parse config: {}", err),
+            ConfigError::Invalid { field, reason } => {
+                write!(f, "invalid config field '{}': {}", field, reason)
+            }
+        }
+    }
+}
+
+impl GameConfig {
+    /// Loads and validates the config at `path`. A missing file is only an
+    /// error if `required` is set, otherwise the defaults are used.
+    pub fn load(path: &Path, required: bool) -> Result<Self, ConfigError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default())
+            }
+            Err(err) => return Err(ConfigError::Io(err)),
+        };
+
+        Self::parse(&contents)
+    }
+
+    /// Reads and validates a config from RON source.
+    fn parse(contents: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            ron::from_str(contents).map_err(|err| ConfigError::Parse(err.to_string()))?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Index into `weapons` of what the player starts with in the current mode.
+    pub fn starting_weapon(&self) -> usize {
+        let name = &self.player.loadout.get(self.mode).weapon;
+
+        self.weapons
+            .iter()
+            .position(|weapon| &weapon.name == name)
+            .expect("validated config names existing weapons")
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        positive("width", self.width)?;
+        positive("height", self.height)?;
+        positive("time_step", self.time_step)?;
+
+        let player = &self.player;
+        positive("player.speed", player.speed)?;
+        size("player.size", player.size)?;
+        hitbox("player.hitbox", player.hitbox)?;
+        nonzero("player.health", player.health as u64)?;
+        nonzero("player.lives", player.lives as u64)?;
+        ascending("player.extra_life_at", &player.extra_life_at)?;
+
+        for mode in GameMode::ALL {
+            let movement = player.movement.get(mode);
+            let field = |name: &str| {
+                let mode = mode.name().to_lowercase();
+                format!("player.movement.{}.{}", mode, name)
+            };
+
+            if let Some(acceleration) = movement.acceleration {
+                positive(&field("acceleration"), acceleration)?;
+            }
+            if let Some(deceleration) = movement.deceleration {
+                positive(&field("deceleration"), deceleration)?;
+            }
+            not_negative(&field("band"), movement.band)?;
+
+            let loadout = player.loadout.get(mode);
+            let field = |name: &str| {
+                let mode = mode.name().to_lowercase();
+                format!("player.loadout.{}.{}", mode, name)
+            };
+
+            if !self
+                .weapons
+                .iter()
+                .any(|weapon| weapon.name == loadout.weapon)
+            {
+                let reason = format!("no weapon named '{}'", loadout.weapon);
+                return Err(invalid(&field("weapon"), reason));
+            }
+            if let Some(max_bullets) = loadout.max_bullets {
+                nonzero(&field("max_bullets"), max_bullets as u64)?;
+            }
+        }
+
+        for (i, weapon) in self.weapons.iter().enumerate() {
+            let field = |name: &str| format!("weapons[{}].{}", i, name);
+            positive(&field("bullet_speed"), weapon.bullet_speed)?;
+            size(&field("bullet_size"), weapon.bullet_size)?;
+            hitbox(&field("bullet_hitbox"), weapon.bullet_hitbox)?;
+
+            if weapon.levels.is_empty() {
+                return Err(invalid(&field("levels"), "must not be empty"));
+            }
+
+            for (j, level) in weapon.levels.iter().enumerate() {
+                let field = |name: &str| format!("weapons[{}].levels[{}].{}", i, j, name);
+                nonzero(&field("damage"), level.damage as u64)?;
+
+                match level.pattern {
+                    ShotPattern::Single => {}
+                    ShotPattern::Twin { gap } => not_negative(&field("pattern.gap"), gap)?,
+                    ShotPattern::Spread { count, spread } => {
+                        nonzero(&field("pattern.count"), count as u64)?;
+                        not_negative(&field("pattern.spread"), spread)?;
+                    }
+                    ShotPattern::Laser { pierce } => {
+                        nonzero(&field("pattern.pierce"), pierce as u64)?
+                    }
+                    ShotPattern::Homing { count, turn_rate } => {
+                        nonzero(&field("pattern.count"), count as u64)?;
+                        positive(&field("pattern.turn_rate"), turn_rate)?;
+                    }
+                }
+            }
+        }
+
+        let enemies = &self.enemies;
+        nonzero("enemies.formation_size", enemies.formation_size as u64)?;
+        positive("enemies.bullet_speed", enemies.bullet_speed)?;
+        size("enemies.bullet_size", enemies.bullet_size)?;
+        hitbox("enemies.bullet_hitbox", enemies.bullet_hitbox)?;
+        nonzero("enemies.bullet_damage", enemies.bullet_damage as u64)?;
+        nonzero("enemies.contact_damage", enemies.contact_damage as u64)?;
+        probability("enemies.fire_jitter", enemies.fire_jitter as f64)?;
+
+        for (i, path) in enemies.paths.iter().enumerate() {
+            let field = |name: &str| format!("enemies.paths[{}].{}", i, name);
+            not_negative(&field("scatter.0"), path.scatter.0)?;
+            not_negative(&field("scatter.1"), path.scatter.1)?;
+
+            if path.steps.is_empty() {
+                return Err(invalid(&field("steps"), "must not be empty"));
+            }
+
+            for (j, step) in path.steps.iter().enumerate() {
+                let field = |name: &str| field(&format!("steps[{}].{}", j, name));
+
+                match step.duration {
+                    Some(duration) => nonzero(&field("duration"), duration)?,
+                    None if j + 1 < path.steps.len() => {
+                        let reason = "must be set on all but the last step";
+                        return Err(invalid(&field("duration"), reason));
+                    }
+                    None => {}
+                }
+
+                match &step.movement {
+                    MovementPath::Ellipse | MovementPath::Dive => {}
+                    MovementPath::Spline { points } | MovementPath::Bezier { points } => {
+                        if points.len() < 2 {
+                            return Err(invalid(&field("movement.points"), "needs at least two"));
+                        }
+                        if step.duration.is_none() {
+                            let reason = "must be set for a curve";
+                            return Err(invalid(&field("duration"), reason));
+                        }
+                    }
+                    MovementPath::Sine { amplitude, period } => {
+                        not_negative(&field("movement.amplitude"), *amplitude)?;
+                        nonzero(&field("movement.period"), *period)?;
+                    }
+                    MovementPath::Hover { sway, period } => {
+                        if j == 0 {
+                            let reason = "a path can't start by hovering";
+                            return Err(invalid(&field("movement"), reason));
+                        }
+                        not_negative(&field("movement.sway"), *sway)?;
+                        nonzero(&field("movement.period"), *period)?;
+                    }
+                }
+            }
+        }
+
+        for (i, pattern) in enemies.patterns.iter().enumerate() {
+            let field = |name: &str| format!("enemies.patterns[{}].{}", i, name);
+            nonzero(&field("burst"), pattern.burst as u64)?;
+            positive(&field("speed"), pattern.speed)?;
+
+            if pattern.shots.is_empty() {
+                return Err(invalid(&field("shots"), "must not be empty"));
+            }
+
+            for (j, shot) in pattern.shots.iter().enumerate() {
+                let field = |name: &str| field(&format!("shots[{}].{}", j, name));
+
+                match *shot {
+                    Shot::Straight { .. } => {}
+                    Shot::Aimed { count, spread } | Shot::Fan { count, spread } => {
+                        nonzero(&field("count"), count as u64)?;
+                        not_negative(&field("spread"), spread)?;
+                    }
+                    Shot::Ring { count } | Shot::Spiral { count, .. } => {
+                        nonzero(&field("count"), count as u64)?;
+                    }
+                }
+            }
+        }
+
+        if !enemies.kinds.iter().any(|kind| kind.from_wave <= 1) {
+            return Err(invalid("enemies.kinds", "no kind shows up in wave 1"));
+        }
+
+        for (i, kind) in enemies.kinds.iter().enumerate() {
+            let field = |name: &str| format!("enemies.kinds[{}].{}", i, name);
+            nonzero(&field("health"), kind.health as u64)?;
+            size(&field("size"), kind.size)?;
+            hitbox(&field("hitbox"), kind.hitbox)?;
+            positive(&field("speed"), kind.speed)?;
+            nonzero(&field("fire_interval"), kind.fire_interval)?;
+
+            if enemies.path(kind).is_none() {
+                let reason = format!("no path named '{}'", kind.path);
+                return Err(invalid(&field("path"), reason));
+            }
+
+            if let Some(name) = &kind.fire {
+                if enemies.fire_pattern(kind).is_none() {
+                    let reason = format!("no fire pattern named '{}'", name);
+                    return Err(invalid(&field("fire"), reason));
+                }
+            }
+            probability(&field("drops.chance"), kind.drops.chance)?;
+            nonzero(&field("weight"), kind.weight as u64)?;
+
+            color(&field("color"), kind.color)?;
+
+            if kind.drops.weights.iter().any(|(_, weight)| *weight == 0) {
+                return Err(invalid(&field("drops.weights"), "weights must not be zero"));
+            }
+        }
+
+        let waves = &self.waves;
+        for (i, wave) in waves.scripted.iter().enumerate() {
+            let field = |name: &str| format!("waves.scripted[{}].{}", i, name);
+            nonzero(&field("enemies"), wave.enemies as u64)?;
+            nonzero(&field("max_active"), wave.max_active as u64)?;
+            nonzero(&field("spawn_interval"), wave.spawn_interval)?;
+            positive(&field("speed"), wave.speed)?;
+            positive(&field("fire_rate"), wave.fire_rate)?;
+
+            for name in &wave.kinds {
+                if !enemies.kinds.iter().any(|kind| &kind.name == name) {
+                    let reason = format!("no enemy kind named '{}'", name);
+                    return Err(invalid(&field("kinds"), reason));
+                }
+            }
+        }
+
+        let ramp = &waves.ramp;
+        not_negative("waves.ramp.max_active", ramp.max_active)?;
+        not_negative("waves.ramp.speed", ramp.speed)?;
+        positive("waves.ramp.speed_cap", ramp.speed_cap)?;
+        not_negative("waves.ramp.fire_rate", ramp.fire_rate)?;
+        positive("waves.ramp.fire_rate_cap", ramp.fire_rate_cap)?;
+
+        for (i, boss) in self.bosses.roster.iter().enumerate() {
+            let field = |name: &str| format!("bosses.roster[{}].{}", i, name);
+            nonzero(&field("health"), boss.health as u64)?;
+            size(&field("size"), boss.size)?;
+            hitbox(&field("hitbox"), boss.hitbox)?;
+            color(&field("color"), boss.color)?;
+            positive(&field("speed"), boss.speed)?;
+            not_negative(&field("hover_at"), boss.hover_at)?;
+
+            for (j, weak_point) in boss.weak_points.iter().enumerate() {
+                let field = |name: &str| field(&format!("weak_points[{}].{}", j, name));
+                size(&field("size"), weak_point.size)?;
+                nonzero(&field("multiplier"), weak_point.multiplier as u64)?;
+            }
+
+            if boss.phases.is_empty() {
+                return Err(invalid(&field("phases"), "must not be empty"));
+            }
+
+            let mut last_until = 1.0;
+            for (j, phase) in boss.phases.iter().enumerate() {
+                let field = |name: &str| field(&format!("phases[{}].{}", j, name));
+
+                if !(0.0..last_until).contains(&phase.until) {
+                    let reason = format!("must be below {} and not negative", last_until);
+                    return Err(invalid(&field("until"), reason));
+                }
+                last_until = phase.until;
+
+                for (k, attack) in phase.attacks.iter().enumerate() {
+                    let field = |name: &str| field(&format!("attacks[{}].{}", k, name));
+                    nonzero(&field("interval"), attack.interval)?;
+
+                    match attack.pattern {
+                        AttackPattern::Aimed { count, spread } => {
+                            nonzero(&field("pattern.count"), count as u64)?;
+                            not_negative(&field("pattern.spread"), spread)?;
+                        }
+                        AttackPattern::Radial { count } => {
+                            nonzero(&field("pattern.count"), count as u64)?;
+                        }
+                        AttackPattern::Laser {
+                            width,
+                            duration,
+                            damage,
+                            ..
+                        } => {
+                            positive(&field("pattern.width"), width)?;
+                            nonzero(&field("pattern.duration"), duration)?;
+                            nonzero(&field("pattern.damage"), damage as u64)?;
+                        }
+                    }
+                }
+
+                match phase.movement {
+                    BossMovement::Hover => {}
+                    BossMovement::Sweep { period, .. }
+                    | BossMovement::FigureEight { period, .. } => {
+                        nonzero(&field("movement.period"), period)?;
+                    }
+                }
+            }
+
+            if last_until != 0.0 {
+                let reason = "the last phase must run until 0";
+                return Err(invalid(&field("phases"), reason));
+            }
+        }
+
+        if self.bosses.every > 0 && self.bosses.roster.is_empty() {
+            return Err(invalid(
+                "bosses.roster",
+                "must not be empty while bosses are on",
+            ));
+        }
+
+        let classic = &self.classic;
+        nonzero("classic.columns", classic.columns as u64)?;
+        nonzero("classic.rows", classic.rows as u64)?;
+        size("classic.size", classic.size)?;
+        hitbox("classic.hitbox", classic.hitbox)?;
+        size("classic.spacing", classic.spacing)?;
+        not_negative("classic.step", classic.step)?;
+        not_negative("classic.drop", classic.drop)?;
+        nonzero("classic.min_march_interval", classic.min_march_interval)?;
+        nonzero("classic.fire_interval", classic.fire_interval)?;
+
+        if classic.march_interval < classic.min_march_interval {
+            let reason = "must not be below classic.min_march_interval";
+            return Err(invalid("classic.march_interval", reason));
+        }
+
+        if classic.row_kinds.is_empty() {
+            return Err(invalid("classic.row_kinds", "must not be empty"));
+        }
+
+        for name in &classic.row_kinds {
+            if !enemies.kinds.iter().any(|kind| &kind.name == name) {
+                let reason = format!("no enemy kind named '{}'", name);
+                return Err(invalid("classic.row_kinds", reason));
+            }
+        }
+
+        for mode in GameMode::ALL {
+            let layout = self.bunkers.layout(mode);
+            let field = |name: &str| format!("bunkers.{}.{}", mode.name().to_lowercase(), name);
+            size(&field("cell_size"), layout.cell_size)?;
+            nonzero(&field("cell_health"), layout.cell_health as u64)?;
+            not_negative(&field("blast_radius"), layout.blast_radius)?;
+            not_negative(&field("height"), layout.height)?;
+
+            if layout.count > 0 && layout.cells().is_empty() {
+                return Err(invalid(&field("shape"), "has no cells"));
+            }
+        }
+
+        let ufo = &self.ufo;
+        nonzero("ufo.interval", ufo.interval)?;
+        positive("ufo.speed", ufo.speed)?;
+        size("ufo.size", ufo.size)?;
+        hitbox("ufo.hitbox", ufo.hitbox)?;
+        color("ufo.color", ufo.color)?;
+        not_negative("ufo.top", ufo.top)?;
+        probability("ufo.drops.chance", ufo.drops.chance)?;
+
+        if ufo.points.is_empty() {
+            return Err(invalid("ufo.points", "must not be empty"));
+        }
+
+        if ufo.drops.weights.iter().any(|(_, weight)| *weight == 0) {
+            return Err(invalid("ufo.drops.weights", "weights must not be zero"));
+        }
+
+        let powerups = &self.powerups;
+        size("powerups.size", powerups.size)?;
+        hitbox("powerups.hitbox", powerups.hitbox)?;
+        positive("powerups.fall_speed", powerups.fall_speed)?;
+        nonzero("powerups.duration", powerups.duration)?;
+        positive("powerups.speed_boost", powerups.speed_boost)?;
+        nonzero(
+            "powerups.score_multiplier",
+            powerups.score_multiplier as u64,
+        )?;
+        not_negative("powerups.spread", powerups.spread)?;
+
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        field: field.to_owned(),
+        reason: reason.into(),
+    }
+}
+
+fn positive(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(field, format!("must be positive, got {}", value)))
+    }
+}
+
+fn not_negative(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid(
+            field,
+            format!("must not be negative, got {}", value),
+        ))
+    }
+}
+
+fn nonzero(field: &str, value: u64) -> Result<(), ConfigError> {
+    if value > 0 {
+        Ok(())
+    } else {
+        Err(invalid(field, "must not be zero"))
+    }
+}
+
+fn size(field: &str, (w, h): (f32, f32)) -> Result<(), ConfigError> {
+    positive(field, w)?;
+    positive(field, h)
+}
+
+fn hitbox(field: &str, hitbox: Option<Hitbox>) -> Result<(), ConfigError> {
+    match hitbox {
+        None => Ok(()),
+        Some(Hitbox::Aabb(box_size)) => size(field, box_size),
+        Some(Hitbox::Circle(radius)) => positive(field, radius),
+    }
+}
+
+fn color(field: &str, (r, g, b): (f32, f32, f32)) -> Result<(), ConfigError> {
+    not_negative(&format!("{}.0", field), r)?;
+    not_negative(&format!("{}.1", field), g)?;
+    not_negative(&format!("{}.2", field), b)
+}
+
+fn probability(field: &str, value: f64) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(
+            field,
+            format!("must be between 0 and 1, got {}", value),
+        ))
+    }
+}
+
+fn ascending(field: &str, values: &[u32]) -> Result<(), ConfigError> {
+    if values.windows(2).all(|pair| pair[0] < pair[1]) {
+        Ok(())
+    } else {
+        Err(invalid(field, "must be in ascending order"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Name of the field `source` is rejected for.
+    fn invalid_field(source: &str) -> String {
+        match GameConfig::parse(source) {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected an invalid field, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bundled_config_is_valid() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(CONFIG_FILE);
+
+        if let Err(err) = GameConfig::load(&path, true) {
+            panic!("{}", err);
+        }
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(GameConfig::default().validate().is_ok());
+        assert!(GameConfig::parse("()").is_ok());
+    }
+
+    #[test]
+    fn error_names_the_field() {
+        let err = GameConfig::parse("(player: (speed: -1.0))").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid config field 'player.speed': must be positive, got -1"
+        );
+    }
+
+    #[test]
+    fn unknown_fire_pattern_is_rejected() {
+        let source = r#"(enemies: (kinds: [(fire: Some("nope"))]))"#;
+        assert_eq!(invalid_field(source), "enemies.kinds[0].fire");
+    }
+
+    #[test]
+    fn drop_chance_must_be_a_probability() {
+        let source = "(enemies: (kinds: [(), (drops: (chance: 1.5))]))";
+        assert_eq!(invalid_field(source), "enemies.kinds[1].drops.chance");
+
+        let source = "(ufo: (drops: (chance: -0.1)))";
+        assert_eq!(invalid_field(source), "ufo.drops.chance");
+    }
+
+    #[test]
+    fn color_channels_must_not_be_negative() {
+        let source = "(enemies: (kinds: [(color: (0.5, 0.5, -0.5))]))";
+        assert_eq!(invalid_field(source), "enemies.kinds[0].color.2");
+
+        let source = "(bosses: (roster: [(color: (-1.0, 0.0, 0.0))]))";
+        assert_eq!(invalid_field(source), "bosses.roster[0].color.0");
+
+        let source = "(ufo: (color: (1.0, -0.2, 0.0)))";
+        assert_eq!(invalid_field(source), "ufo.color.1");
+    }
+
+    #[test]
+    fn unknown_keys_fail_to_parse() {
+        let result = GameConfig::parse("(player: (sped: 100.0))");
+        assert!(matches!(result, Err(ConfigError::Parse(_))));
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{data_path, load_ron, save_ron};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+const CONTROLS_FILE: &str = "controls.ron";
+/// Overrides where the bindings are stored.
+const CONTROLS_PATH_VAR: &str = "RUST_INVADERS_CONTROLS";
+/// How far a stick has to be pushed to count as pressed.
+const STICK_THRESHOLD: f32 = 0.5;
+/// Stick travel ignored around the center, so a worn stick doesn't drift.
+const STICK_DEADZONE: f32 = 0.15;
+
+/// Turns keys, buttons and sticks into the actions they are bound to, once
+/// per frame, before anything reads them.
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = data_path(CONTROLS_FILE, CONTROLS_PATH_VAR);
+        let bindings = Bindings::load(&path);
+
+        app.insert_resource(Controls::new(bindings))
+            .insert_resource(ControlsPath(path))
+            .add_system_to_stage(CoreStage::PreUpdate, read_controls.after(InputSystem));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Fire,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Fire,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::Fire => "Fire",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sign {
+    Negative,
+    Positive,
+}
+
+/// One key, button or stick direction an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    /// A button on any gamepad.
+    Button(GamepadButtonType),
+    /// A stick of any gamepad pushed past `STICK_THRESHOLD` one way.
+    Axis(GamepadAxisType, Sign),
+}
+
+impl Binding {
+    pub fn is_gamepad(self) -> bool {
+        !matches!(self, Binding::Key(_))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Button(button) => write!(f, "Pad {:?}", button),
+            Binding::Axis(axis, Sign::Negative) => write!(f, "Pad {:?}-", axis),
+            Binding::Axis(axis, Sign::Positive) => write!(f, "Pad {:?}+", axis),
+        }
+    }
+}
+
+/// What each action is bound to, saved between runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Bindings {
+    /// Loads the bindings, falling back to the defaults for the file or any
+    /// action missing from it.
+    pub fn load(path: &Path) -> Self {
+        let mut bindings: Self = match load_ron(path, "controls") {
+            Some(bindings) => bindings,
+            None => return Self::default(),
+        };
+
+        for (action, defaults) in Self::default().0 {
+            bindings.0.entry(action).or_insert(defaults);
+        }
+        bindings
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_ron(path, self)
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `action` to `binding` in place of whatever it had on the same
+    /// kind of device, keyboard or gamepad.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bound = self.0.entry(action).or_default();
+        bound.retain(|other| other.is_gamepad() != binding.is_gamepad());
+        bound.insert(0, binding);
+    }
+
+    /// Rebinds `action` to the `pressed` binding, unless that's Escape, which
+    /// cancels instead. Returns whether `action` was rebound.
+    pub fn rebind_unless_cancelled(&mut self, action: Action, pressed: Binding) -> bool {
+        // Sticks to the keyboard, whatever the bindings, so a bad one can
+        // always be backed out of.
+        if pressed == Binding::Key(KeyCode::Escape) {
+            return false;
+        }
+
+        self.rebind(action, pressed);
+        true
+    }
+
+    /// The main binding of `action`, for on-screen prompts.
+    pub fn describe(&self, action: Action) -> String {
+        self.get(action)
+            .first()
+            .map_or_else(|| String::from("(unbound)"), Binding::to_string)
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = Binding::Key;
+        let button = Binding::Button;
+        let stick = Binding::Axis;
+
+        let bindings = [
+            (
+                Action::MoveLeft,
+                vec![
+                    key(KeyCode::A),
+                    key(KeyCode::Left),
+                    button(GamepadButtonType::DPadLeft),
+                    stick(GamepadAxisType::LeftStickX, Sign::Negative),
+                ],
+            ),
+            (
+                Action::MoveRight,
+                vec![
+                    key(KeyCode::D),
+                    key(KeyCode::Right),
+                    button(GamepadButtonType::DPadRight),
+                    stick(GamepadAxisType::LeftStickX, Sign::Positive),
+                ],
+            ),
+            (
+                Action::MoveUp,
+                vec![
+                    key(KeyCode::W),
+                    key(KeyCode::Up),
+                    button(GamepadButtonType::DPadUp),
+                    stick(GamepadAxisType::LeftStickY, Sign::Positive),
+                ],
+            ),
+            (
+                Action::MoveDown,
+                vec![
+                    key(KeyCode::S),
+                    key(KeyCode::Down),
+                    button(GamepadButtonType::DPadDown),
+                    stick(GamepadAxisType::LeftStickY, Sign::Negative),
+                ],
+            ),
+            (
+                Action::Fire,
+                vec![
+                    key(KeyCode::Space),
+                    button(GamepadButtonType::South),
+                    button(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (
+                Action::Pause,
+                vec![key(KeyCode::Escape), button(GamepadButtonType::Start)],
+            ),
+            (
+                Action::Confirm,
+                vec![
+                    key(KeyCode::Return),
+                    key(KeyCode::Space),
+                    button(GamepadButtonType::South),
+                ],
+            ),
+            (
+                Action::Back,
+                vec![key(KeyCode::Escape), button(GamepadButtonType::East)],
+            ),
+        ];
+
+        Self(bindings.into_iter().collect())
+    }
+}
+
+// -- Resources --
+/// The bindings, and which of them are held this frame.
+#[derive(Default)]
+pub struct Controls {
+    pub bindings: Bindings,
+    held: Vec<Binding>,
+    /// Held last frame, to tell fresh presses apart.
+    previous: Vec<Binding>,
+    /// Consumed while held, ignored until released.
+    suppressed: Vec<Binding>,
+    /// First input pressed this frame, bound or not.
+    latest: Option<Binding>,
+    /// How far each stick direction is pushed past the dead zone, from 0 to 1.
+    sticks: Vec<(Binding, f32)>,
+}
+
+impl Controls {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            ..Default::default()
+        }
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|binding| self.held.contains(binding))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings
+            .get(action)
+            .iter()
+            .any(|binding| self.held.contains(binding) && !self.previous.contains(binding))
+    }
+
+    /// How far `action` is pushed, from 0 to 1: all the way for keys and
+    /// buttons, as far as the stick goes for sticks.
+    pub fn strength(&self, action: Action) -> f32 {
+        self.bindings
+            .get(action)
+            .iter()
+            .map(|binding| match binding {
+                Binding::Axis(..) if !self.suppressed.contains(binding) => self
+                    .sticks
+                    .iter()
+                    .find(|(stick, _)| stick == binding)
+                    .map_or(0.0, |(_, push)| *push),
+                _ if self.held.contains(binding) => 1.0,
+                _ => 0.0,
+            })
+            .fold(0.0, f32::max)
+    }
+
+    /// Releases `action` until its inputs are let go of, along with any other
+    /// action sharing them. Transitions happen within the same frame, so this
+    /// keeps the next state from seeing the press that triggered them.
+    pub fn consume(&mut self, action: Action) {
+        let bindings = self.bindings.get(action);
+        let (consumed, held): (Vec<Binding>, Vec<Binding>) = self
+            .held
+            .iter()
+            .partition(|binding| bindings.contains(binding));
+
+        self.held = held;
+        self.suppressed.extend(consumed);
+        self.latest = None;
+    }
+
+    pub fn latest(&self) -> Option<Binding> {
+        self.latest
+    }
+}
+
+pub struct ControlsPath(pub PathBuf);
+
+fn read_controls(
+    mut controls: ResMut<Controls>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+) {
+    let mut held: Vec<Binding> = keys.get_pressed().map(|key| Binding::Key(*key)).collect();
+
+    // Several gamepads may hold the same thing.
+    let mut hold = |binding| {
+        if !held.contains(&binding) {
+            held.push(binding);
+        }
+    };
+
+    for GamepadButton(_, button) in buttons.get_pressed() {
+        hold(Binding::Button(*button));
+    }
+
+    let mut sticks: Vec<(Binding, f32)> = Vec::new();
+
+    for gamepad in gamepads.iter() {
+        for axis in [
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        ] {
+            let value = axes.get(GamepadAxis(*gamepad, axis)).unwrap_or(0.0);
+            let sign = if value < 0.0 {
+                Sign::Negative
+            } else {
+                Sign::Positive
+            };
+            let binding = Binding::Axis(axis, sign);
+
+            if value.abs() >= STICK_THRESHOLD {
+                hold(binding);
+            }
+
+            let push = ((value.abs() - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).clamp(0.0, 1.0);
+
+            match sticks.iter_mut().find(|(stick, _)| *stick == binding) {
+                Some((_, furthest)) => *furthest = furthest.max(push),
+                None if push > 0.0 => sticks.push((binding, push)),
+                None => {}
+            }
+        }
+    }
+
+    let controls = &mut *controls;
+    controls.suppressed.retain(|binding| held.contains(binding));
+    held.retain(|binding| !controls.suppressed.contains(binding));
+
+    controls.latest = held
+        .iter()
+        .find(|binding| !controls.held.contains(binding))
+        .copied();
+    controls.previous = std::mem::replace(&mut controls.held, held);
+    controls.sticks = sticks;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_path;
+    use std::fs;
+
+    #[test]
+    fn corrupt_file_loads_defaults() {
+        let path = temp_path("controls-corrupt");
+        fs::write(&path, "({MoveLeft: [Key(").unwrap();
+
+        assert_eq!(Bindings::load(&path), Bindings::default());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_actions_get_their_defaults() {
+        let path = temp_path("controls-partial");
+        fs::write(&path, "({Fire: [Key(J)]})").unwrap();
+
+        let bindings = Bindings::load(&path);
+        assert_eq!(bindings.get(Action::Fire), [Binding::Key(KeyCode::J)]);
+        assert_eq!(
+            bindings.get(Action::Pause),
+            Bindings::default().get(Action::Pause)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rebind_replaces_only_the_same_device() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::MoveLeft, Binding::Key(KeyCode::J));
+
+        assert_eq!(
+            bindings.get(Action::MoveLeft),
+            [
+                Binding::Key(KeyCode::J),
+                Binding::Button(GamepadButtonType::DPadLeft),
+                Binding::Axis(GamepadAxisType::LeftStickX, Sign::Negative),
+            ]
+        );
+    }
+
+    #[test]
+    fn escape_cancels_a_rebind() {
+        let mut bindings = Bindings::default();
+
+        assert!(!bindings.rebind_unless_cancelled(Action::Fire, Binding::Key(KeyCode::Escape)));
+        assert_eq!(bindings, Bindings::default());
+
+        let pressed = Binding::Button(GamepadButtonType::West);
+        assert!(bindings.rebind_unless_cancelled(Action::Fire, pressed));
+        assert_eq!(bindings.get(Action::Fire)[0], pressed);
+    }
+}
@@ -1,43 +1,101 @@
 use crate::{
-    player::{Player, PlayerState},
-    Materials, Speed, WinSize, TIME_STEP,
+    collision::{Collider, DespawnOnHit, DespawnOutOfBounds, Layer, Shape},
+    config::{GameConfig, GameMode, MovementPath, Shot},
+    despawn_all,
+    health::{Damage, DeathEvent, Health},
+    player::Player,
+    powerups::spawn_bonus,
+    scores::Scorer,
+    sim::{Interpolated, SimTime, SIMULATION},
+    waves::WaveManager,
+    GameState, Materials, Spawner, Speed, Velocity, WinSize,
+};
+use bevy::prelude::*;
+use rand::Rng;
+use std::{
+    collections::HashSet,
+    f32::consts::{FRAC_PI_2, PI, TAU},
 };
-use bevy::{core::FixedTimestep, prelude::*, sprite::collide_aabb::collide};
-use rand::{thread_rng, Rng};
-use std::f32::consts::PI;
 
-const MAX_ENEMIES: u32 = 3;
-const FORMATION_SIZE: u32 = 2;
+/// How far past the playfield enemy bullets fly on; some are fired from just off screen.
+const BULLET_MARGIN: f32 = 50.0;
+/// How far past the bottom enemies go before coming around from the top.
+const WRAP_MARGIN: f32 = 50.0;
+/// How many times its speed an enemy may move to keep up with its path.
+const CATCH_UP: f32 = 2.0;
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ActiveEnemies(0))
+        app.insert_resource(ActiveEnemies::default())
             .insert_resource(FormationMaker::default())
-            .add_system(enemy_movement)
-            .add_system(e_bullet_movement)
-            .add_system(e_bullet_hit)
-            .add_system_set(
+            .add_system_set_to_stage(
+                SIMULATION,
                 SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(1.0))
-                    .with_system(spawn_enemy),
+                    .with_system(count_enemies)
+                    .with_system(enemy_movement)
+                    .with_system(e_bullet_movement)
+                    .with_system(enemy_death)
+                    .with_system(spawn_enemy)
+                    .with_system(enemy_fire),
             )
             .add_system_set(
-                SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(0.85))
-                    .with_system(enemy_fire),
-            );
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(despawn_all::<Enemy>)
+                    .with_system(despawn_all::<EnemyBullet>)
+                    .with_system(reset_enemies),
+            )
+            // Removals are only tracked until the end of the frame, so the
+            // ones left by the frame's last tick are picked up here.
+            .add_system_to_stage(CoreStage::PostUpdate, count_enemies);
+
+        #[cfg(debug_assertions)]
+        app.add_system_to_stage(SIMULATION, check_enemy_count);
     }
 }
 
 // -- Components --
+/// An enemy of the kind at this index in the config's `enemies.kinds`.
 #[derive(Component)]
-pub struct Enemy;
-#[derive(Component)]
-pub struct ActiveEnemies(pub u32);
+pub struct Enemy(pub usize);
 #[derive(Component)]
 pub struct EnemyBullet;
+/// What an enemy fires, and when it fires next.
+#[derive(Component)]
+pub struct Gun {
+    /// Index into the config's `enemies.patterns`.
+    pattern: usize,
+    next: u64,
+    /// Volleys left in the current burst.
+    burst_left: u32,
+    /// Volleys fired so far, to turn spirals.
+    volleys: u32,
+}
+/// Where an enemy is along its kind's path.
+#[derive(Component)]
+pub struct PathFollower {
+    /// Index into the config's `enemies.paths`.
+    path: usize,
+    step: usize,
+    step_started: u64,
+    /// Where the enemy was when the step started.
+    anchor: Vec2,
+    /// -1 when the path is mirrored left to right.
+    flip: f32,
+    /// How far the whole path is shifted, in fractions of the half playfield.
+    shift: Vec2,
+    /// Multiplier on how fast the path goes by, from the wave.
+    pace: f32,
+}
+
+impl PathFollower {
+    /// Where a point of the path, given in fractions of the half playfield, lies.
+    fn point(&self, (x, y): (f32, f32), win_size: &WinSize) -> Vec2 {
+        let point = Vec2::new(x * self.flip, y) + self.shift;
+        point * Vec2::new(win_size.w / 2.0, win_size.h / 2.0)
+    }
+}
 
 #[derive(Component, Default, Clone)]
 pub struct Formation {
@@ -49,6 +107,18 @@ pub struct Formation {
     id: u32,
 }
 
+// -- Resources --
+/// Enemies alive, as of the start of the tick. Follows the enemies coming
+/// and going in the world, whatever spawned or despawned them.
+#[derive(Default)]
+pub struct ActiveEnemies(HashSet<Entity>);
+
+impl ActiveEnemies {
+    pub fn count(&self) -> u32 {
+        self.0.len() as u32
+    }
+}
+
 #[derive(Component, Default)]
 pub struct FormationMaker {
     seq_id: u32,
@@ -57,14 +127,9 @@ pub struct FormationMaker {
 }
 
 impl FormationMaker {
-    fn make(&mut self, win_size: &Res<WinSize>) -> Formation {
-        match (
-            self.current.as_ref(),
-            self.current_members >= FORMATION_SIZE,
-        ) {
+    fn make(&mut self, size: u32, win_size: &Res<WinSize>, rng: &mut impl Rng) -> Formation {
+        match (self.current.as_ref(), self.current_members >= size) {
             (None, _) | (_, true) => {
-                let mut rng = thread_rng();
-
                 let h = win_size.h / 2.0 - 100.0;
                 let w = win_size.w / 4.0;
 
@@ -106,155 +171,547 @@ impl FormationMaker {
     }
 }
 
-fn spawn_enemy(
-    mut commands: Commands,
+fn reset_enemies(mut formation_maker: ResMut<FormationMaker>) {
+    *formation_maker = FormationMaker::default();
+}
+
+fn count_enemies(
     mut active_enemies: ResMut<ActiveEnemies>,
-    mut formation_maker: ResMut<FormationMaker>,
-    win_size: Res<WinSize>,
-    materials: Res<Materials>,
+    added: Query<Entity, Added<Enemy>>,
+    removed: RemovedComponents<Enemy>,
 ) {
-    if active_enemies.0 < MAX_ENEMIES {
-        let formation = formation_maker.make(&win_size);
-        let (x, y) = formation.start;
-
-        commands
-            .spawn_bundle(SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(36.0, 12.0)),
-                    color: materials.enemy,
-                    ..Default::default()
-                },
-                transform: Transform {
-                    translation: Vec3::new(x, y, 10.0),
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(Enemy)
-            .insert(Speed::default())
-            .insert(formation);
-
-        active_enemies.0 += 1;
+    for entity in removed.iter() {
+        active_enemies.0.remove(&entity);
     }
+
+    active_enemies.0.extend(added.iter());
+}
+
+/// Catches the count drifting from the enemies actually around, in debug builds.
+#[cfg(debug_assertions)]
+fn check_enemy_count(active_enemies: Res<ActiveEnemies>, query: Query<(), With<Enemy>>) {
+    assert_eq!(
+        active_enemies.count() as usize,
+        query.iter().count(),
+        "ActiveEnemies out of step with the enemies in the world"
+    );
 }
 
-fn enemy_movement(mut query: Query<(&mut Transform, &Speed, &mut Formation), With<Enemy>>) {
-    for (mut transform, speed, mut formation) in query.iter_mut() {
-        let max_dist = TIME_STEP * speed.0;
-        let x_origin = transform.translation.x;
-        let y_origin = transform.translation.y;
+fn spawn_enemy(
+    spawner: Spawner,
+    active_enemies: Res<ActiveEnemies>,
+    mut formation_maker: ResMut<FormationMaker>,
+    mut waves: ResMut<WaveManager>,
+    sim_time: Res<SimTime>,
+) {
+    let Spawner {
+        mut commands,
+        mut rng,
+        config,
+        win_size,
+        ..
+    } = spawner;
+
+    if config.mode != GameMode::Arcade {
+        return;
+    }
 
-        let (x_offset, y_offset) = formation.offset;
-        let (x_radius, y_radius) = formation.radius;
+    let config = &config.enemies;
 
-        let dir = if formation.start.0 > 0.0 { 1.0 } else { -1.0 };
-        let angle =
-            formation.angle + dir * speed.0 * TIME_STEP / (x_radius.min(y_radius) * PI / 2.0);
+    let now = sim_time.ticks();
 
-        let x_dest = x_radius * angle.cos() + x_offset;
-        let y_dest = y_radius * angle.sin() + y_offset;
+    if waves.can_spawn(now, active_enemies.count()) {
+        let wave = waves.wave();
+        let index = config.pick_kind(waves.number(), &wave.kinds, &mut *rng);
+        let kind = &config.kinds[index];
 
-        let (dx, dy) = (x_origin - x_dest, y_origin - y_dest);
+        let path_index = config
+            .path(kind)
+            .expect("validated config names existing paths");
+        let path = &config.paths[path_index];
 
-        let distance = (dx * dx + dy * dy).sqrt();
-        let dist_ratio = if distance == 0.0 {
-            0.0
+        let flip = if path.mirror && rng.gen_bool(0.5) {
+            -1.0
         } else {
-            max_dist / distance
+            1.0
+        };
+        let (scatter_x, scatter_y) = path.scatter;
+        let shift = Vec2::new(
+            rng.gen_range(-scatter_x..=scatter_x),
+            rng.gen_range(-scatter_y..=scatter_y),
+        );
+        let follower = PathFollower {
+            path: path_index,
+            step: 0,
+            step_started: now,
+            anchor: Vec2::ZERO,
+            flip,
+            shift,
+            pace: wave.speed,
         };
 
-        let x = x_origin - dx * dist_ratio;
-        let y = y_origin - dy * dist_ratio;
+        let formation = path
+            .steps
+            .iter()
+            .any(|step| step.movement == MovementPath::Ellipse)
+            .then(|| formation_maker.make(config.formation_size, &win_size, &mut *rng));
 
-        let x = if dx > 0.0 {
-            x.max(x_dest)
-        } else {
-            x.min(x_dest)
+        let start = match (&path.steps[0].movement, &formation) {
+            (MovementPath::Spline { points } | MovementPath::Bezier { points }, _) => {
+                follower.point(points[0], &win_size)
+            }
+            (MovementPath::Ellipse, Some(formation)) => Vec2::from(formation.start),
+            // Anything else enters from above, somewhere over the playfield.
+            _ => Vec2::new(
+                rng.gen_range(-win_size.w / 2.0..win_size.w / 2.0),
+                win_size.h / 2.0 + kind.size.1,
+            ),
+        };
+        let translation = start.extend(10.0);
+
+        let mut enemy = commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(kind.size)),
+                color: kind.color(),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        enemy
+            .insert(Enemy(index))
+            .insert(Health::new(kind.health))
+            .insert(
+                Collider::new(Shape::from_config(kind.hitbox, kind.size), Layer::Enemy)
+                    .hits(&[Layer::Bunker, Layer::Player]),
+            )
+            .insert(Damage(config.contact_damage))
+            .insert(Interpolated::new(translation))
+            .insert(Speed(kind.speed * wave.speed))
+            .insert(PathFollower {
+                anchor: start,
+                ..follower
+            });
+
+        if let Some(formation) = formation {
+            enemy.insert(formation);
+        }
+
+        if let Some(pattern) = config.fire_pattern(kind) {
+            // Somewhere in the first interval, so a group doesn't open fire at once.
+            let interval = waves.fire_interval(kind.fire_interval);
+            enemy.insert(Gun {
+                pattern,
+                next: now + rng.gen_range(1..=interval),
+                burst_left: config.patterns[pattern].burst,
+                volleys: 0,
+            });
+        }
+
+        waves.count_spawn(now);
+    }
+}
+
+/// Moves each enemy along the current step of its path, going on to the next
+/// step once the current one runs out.
+fn enemy_movement(
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Interpolated,
+            &Speed,
+            &mut PathFollower,
+            Option<&mut Formation>,
+        ),
+        Without<Player>,
+    >,
+) {
+    let now = sim_time.ticks();
+    let dt = sim_time.dt();
+    let player_x = player_query
+        .get_single()
+        .map_or(0.0, |player_tf| player_tf.translation.x);
+
+    for (mut transform, mut interpolated, speed, mut follower, formation) in query.iter_mut() {
+        let path = &config.enemies.paths[follower.path];
+        let step = &path.steps[follower.step];
+        // Ticks into the step, sped up or slowed down with the wave.
+        let elapsed = (now - follower.step_started) as f32 * follower.pace;
+
+        if step
+            .duration
+            .map_or(false, |duration| elapsed >= duration as f32)
+        {
+            follower.step = (follower.step + 1) % path.steps.len();
+            follower.step_started = now;
+            follower.anchor = transform.translation.truncate();
+        }
+
+        let step = &path.steps[follower.step];
+        let elapsed = (now - follower.step_started) as f32 * follower.pace;
+        // How far through a curve, from 0 to 1.
+        let progress = step
+            .duration
+            .map_or(0.0, |duration| (elapsed / duration as f32).min(1.0));
+
+        let target = match &step.movement {
+            MovementPath::Ellipse => {
+                if let Some(mut formation) = formation {
+                    follow_ellipse(&mut transform, speed.0, &mut formation, dt);
+                }
+                continue;
+            }
+            MovementPath::Dive => {
+                dive(
+                    &mut transform,
+                    &mut interpolated,
+                    speed.0,
+                    player_x,
+                    &win_size,
+                    dt,
+                );
+                continue;
+            }
+            MovementPath::Spline { points } => {
+                let points: Vec<Vec2> = points
+                    .iter()
+                    .map(|point| follower.point(*point, &win_size))
+                    .collect();
+                catmull_rom(&points, progress)
+            }
+            MovementPath::Bezier { points } => {
+                let points: Vec<Vec2> = points
+                    .iter()
+                    .map(|point| follower.point(*point, &win_size))
+                    .collect();
+                bezier(&points, progress)
+            }
+            MovementPath::Sine { amplitude, period } => {
+                let seconds = (now - follower.step_started) as f32 * dt;
+                let sway = follower.flip * amplitude * (TAU * elapsed / *period as f32).sin();
+                let target = follower.anchor + Vec2::new(sway, -speed.0 * seconds);
+
+                // Around from the top again, without gliding all the way back up.
+                if target.y < -win_size.h / 2.0 - WRAP_MARGIN {
+                    follower.anchor.y += win_size.h + 2.0 * WRAP_MARGIN;
+                    let target = target + Vec2::new(0.0, win_size.h + 2.0 * WRAP_MARGIN);
+                    transform.translation.x = target.x;
+                    transform.translation.y = target.y;
+                    interpolated.snap(transform.translation);
+                    continue;
+                }
+
+                target
+            }
+            MovementPath::Hover { sway, period } => {
+                let sway = follower.flip * sway * (TAU * elapsed / *period as f32).sin();
+                follower.anchor + Vec2::new(sway, 0.0)
+            }
         };
 
-        let y = if dy > 0.0 {
-            y.max(y_dest)
+        // Head for the path at up to `CATCH_UP` times top speed, so steps and
+        // restarts glide into each other instead of jumping.
+        let position = transform.translation.truncate();
+        let max_step = speed.0 * CATCH_UP * dt;
+        let position = if position.distance(target) <= max_step {
+            target
         } else {
-            y.min(y_dest)
+            position + (target - position).normalize() * max_step
         };
 
-        if distance < max_dist * speed.0 / 20.0 {
-            formation.angle = angle;
-        }
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Eases toward the next point around the formation's ellipse.
+fn follow_ellipse(transform: &mut Transform, speed: f32, formation: &mut Formation, dt: f32) {
+    let max_dist = dt * speed;
+    let x_origin = transform.translation.x;
+    let y_origin = transform.translation.y;
+
+    let (x_offset, y_offset) = formation.offset;
+    let (x_radius, y_radius) = formation.radius;
+
+    let dir = if formation.start.0 > 0.0 { 1.0 } else { -1.0 };
+    let angle = formation.angle + dir * speed * dt / (x_radius.min(y_radius) * PI / 2.0);
+
+    let x_dest = x_radius * angle.cos() + x_offset;
+    let y_dest = y_radius * angle.sin() + y_offset;
+
+    let (dx, dy) = (x_origin - x_dest, y_origin - y_dest);
+
+    let distance = (dx * dx + dy * dy).sqrt();
+    let dist_ratio = if distance == 0.0 {
+        0.0
+    } else {
+        max_dist / distance
+    };
 
-        transform.translation.x = x;
-        transform.translation.y = y;
+    let x = x_origin - dx * dist_ratio;
+    let y = y_origin - dy * dist_ratio;
+
+    let x = if dx > 0.0 {
+        x.max(x_dest)
+    } else {
+        x.min(x_dest)
+    };
+
+    let y = if dy > 0.0 {
+        y.max(y_dest)
+    } else {
+        y.min(y_dest)
+    };
+
+    if distance < max_dist * speed / 20.0 {
+        formation.angle = angle;
     }
+
+    transform.translation.x = x;
+    transform.translation.y = y;
 }
 
-fn enemy_fire(
-    mut commands: Commands,
-    materials: Res<Materials>,
-    query: Query<&Transform, With<Enemy>>,
+/// Swoops down at the player, wrapping back to the top once past the bottom
+/// of the playfield.
+fn dive(
+    transform: &mut Transform,
+    interpolated: &mut Interpolated,
+    speed: f32,
+    target_x: f32,
+    win_size: &WinSize,
+    dt: f32,
 ) {
-    for transform in query.iter() {
-        let x = transform.translation.x;
-        let y = transform.translation.y;
-
-        commands
-            .spawn_bundle(SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(2.0, 16.0)),
-                    color: materials.e_bullet,
-                    ..Default::default()
-                },
-
-                transform: Transform {
-                    translation: Vec3::new(x, y, 0.0),
-                    ..Default::default()
-                },
+    let translation = &mut transform.translation;
+    let steer = (target_x - translation.x).clamp(-1.0, 1.0);
 
-                ..Default::default()
-            })
-            .insert(EnemyBullet)
-            .insert(Speed::default());
+    translation.x += steer * speed / 2.0 * dt;
+    translation.y -= speed * dt;
+
+    if translation.y < -win_size.h / 2.0 - WRAP_MARGIN {
+        translation.y = win_size.h / 2.0 + WRAP_MARGIN;
+        interpolated.snap(*translation);
     }
 }
 
-fn e_bullet_movement(
-    mut commands: Commands,
-    win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Speed, &mut Transform), With<EnemyBullet>>,
+/// Point `t` of the way, from 0 to 1, along a Catmull-Rom spline through all of `points`.
+fn catmull_rom(points: &[Vec2], t: f32) -> Vec2 {
+    let last = points.len() - 1;
+    let along = t * last as f32;
+    let i = (along as usize).min(last - 1);
+    let t = along - i as f32;
+
+    // The ends stand in for the missing neighbors of the first and last points.
+    let p0 = points[i.saturating_sub(1)];
+    let p1 = points[i];
+    let p2 = points[i + 1];
+    let p3 = points[(i + 2).min(last)];
+
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t)
+}
+
+/// Point `t` of the way, from 0 to 1, along the Bezier curve with control `points`.
+fn bezier(points: &[Vec2], t: f32) -> Vec2 {
+    let mut points = points.to_vec();
+
+    while points.len() > 1 {
+        points = points
+            .windows(2)
+            .map(|pair| pair[0].lerp(pair[1], t))
+            .collect();
+    }
+
+    points[0]
+}
+
+/// Fires an enemy bullet from `position`, moving by `velocity` per second.
+pub fn spawn_enemy_bullet(
+    commands: &mut Commands,
+    config: &GameConfig,
+    materials: &Materials,
+    position: Vec3,
+    velocity: Vec2,
+) {
+    let translation = Vec3::new(position.x, position.y, 0.0);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(config.enemies.bullet_size)),
+                color: materials.e_bullet,
+                ..Default::default()
+            },
+
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+
+            ..Default::default()
+        })
+        .insert(EnemyBullet)
+        .insert(
+            Collider::new(
+                Shape::from_config(config.enemies.bullet_hitbox, config.enemies.bullet_size),
+                Layer::EnemyBullet,
+            )
+            .hits(&[Layer::Player, Layer::Bunker]),
+        )
+        .insert(DespawnOnHit)
+        .insert(DespawnOutOfBounds {
+            margin: BULLET_MARGIN,
+        })
+        .insert(Damage(config.enemies.bullet_damage))
+        .insert(Interpolated::new(translation))
+        .insert(Velocity(velocity));
+}
+
+/// Unit headings of `count` shots fanned over `spread` radians, centered on
+/// the angle `aim`.
+pub fn fan(aim: f32, count: u32, spread: f32) -> Vec<Vec2> {
+    (0..count)
+        .map(|i| {
+            let angle = if count == 1 {
+                aim
+            } else {
+                aim - spread / 2.0 + spread * i as f32 / (count - 1) as f32
+            };
+            Vec2::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+/// Unit headings of `count` shots evenly spaced all around, the first at `start` radians.
+pub fn ring(count: u32, start: f32) -> Vec<Vec2> {
+    (0..count)
+        .map(|i| {
+            let angle = start + TAU * i as f32 / count as f32;
+            Vec2::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+/// The bullets of `shot` as their offset to the side of `origin` and their
+/// heading, for the enemy's `volley`th volley.
+fn aim_shot(shot: Shot, origin: Vec2, target: Vec2, volley: u32) -> Vec<(f32, Vec2)> {
+    let headings = match shot {
+        Shot::Straight { offset } => return vec![(offset, -Vec2::Y)],
+        Shot::Aimed { count, spread } => {
+            let aim = target - origin;
+            fan(aim.y.atan2(aim.x), count, spread)
+        }
+        Shot::Fan { count, spread } => fan(-FRAC_PI_2, count, spread),
+        Shot::Ring { count } => ring(count, 0.0),
+        Shot::Spiral { count, turn } => ring(count, turn * volley as f32),
+    };
+
+    headings.into_iter().map(|heading| (0.0, heading)).collect()
+}
+
+/// `interval` moved by up to `jitter` of itself either way, at random.
+fn jittered(interval: u64, jitter: f32, rng: &mut impl Rng) -> u64 {
+    let spread = (interval as f32 * jitter) as u64;
+    (interval - spread + rng.gen_range(0..=2 * spread)).max(1)
+}
+
+/// Fires each armed enemy's pattern whenever its own timer comes up.
+fn enemy_fire(
+    spawner: Spawner,
+    sim_time: Res<SimTime>,
+    waves: Res<WaveManager>,
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<(&Transform, &Enemy, &mut Gun)>,
 ) {
-    for (entity, speed, mut transform) in query.iter_mut() {
-        transform.translation.y -= speed.0 * TIME_STEP;
+    let Spawner {
+        mut commands,
+        mut rng,
+        config,
+        materials,
+        win_size,
+    } = spawner;
+
+    let now = sim_time.ticks();
+    let target = player_query
+        .get_single()
+        .map_or(Vec2::new(0.0, -win_size.h / 2.0), |player_tf| {
+            player_tf.translation.truncate()
+        });
+
+    for (transform, Enemy(kind), mut gun) in query.iter_mut() {
+        if now < gun.next {
+            continue;
+        }
 
-        if transform.translation.y < -win_size.h / 2.0 - 50.0 {
-            commands.entity(entity).despawn();
+        let kind = &config.enemies.kinds[*kind];
+        let pattern = &config.enemies.patterns[gun.pattern];
+        let speed = config.enemies.bullet_speed * pattern.speed;
+        let origin = transform.translation;
+
+        for shot in &pattern.shots {
+            for (offset, heading) in aim_shot(*shot, origin.truncate(), target, gun.volleys) {
+                let position = origin + Vec3::new(offset, 0.0, 0.0);
+                let velocity = heading * speed;
+                spawn_enemy_bullet(&mut commands, &config, &materials, position, velocity);
+            }
+        }
+
+        gun.volleys += 1;
+
+        if gun.burst_left > 1 {
+            gun.burst_left -= 1;
+            gun.next = now + pattern.burst_gap.max(1);
+        } else {
+            let interval = waves.fire_interval(kind.fire_interval);
+            gun.burst_left = pattern.burst;
+            gun.next = now + jittered(interval, config.enemies.fire_jitter, &mut *rng);
         }
     }
 }
 
-fn e_bullet_hit(
-    mut commands: Commands,
-    mut player_state: ResMut<PlayerState>,
-    time: Res<Time>,
-    bullet_query: Query<(Entity, &Transform, &Sprite), With<EnemyBullet>>,
-    mut player_query: Query<(Entity, &Transform, &Sprite), With<Player>>,
+fn e_bullet_movement(
+    sim_time: Res<SimTime>,
+    mut query: Query<(&Velocity, &mut Transform), With<EnemyBullet>>,
 ) {
-    if let Ok((player, player_tf, player_sprite)) = player_query.get_single_mut() {
-        let player_scale = player_tf.scale.abs().truncate();
-
-        for (bullet, bullet_tf, bullet_sprite) in bullet_query.iter() {
-            let bullet_scale = bullet_tf.scale.abs().truncate();
-
-            if let Some(_collision) = collide(
-                bullet_tf.translation,
-                bullet_sprite.custom_size.unwrap() * bullet_scale,
-                player_tf.translation,
-                player_sprite.custom_size.unwrap() * player_scale,
-            ) {
-                // Kill!
-                commands.entity(bullet).despawn();
-                commands.entity(player).despawn();
-
-                player_state.kill_at_time(time.seconds_since_startup());
-            };
+    for (velocity, mut transform) in query.iter_mut() {
+        transform.translation += (velocity.0 * sim_time.dt()).extend(0.0);
+    }
+}
+
+/// Scores destroyed enemies and rolls their drops.
+fn enemy_death(
+    mut deaths: EventReader<DeathEvent>,
+    spawner: Spawner,
+    mut scorer: Scorer,
+    enemy_query: Query<&Enemy>,
+) {
+    let Spawner {
+        mut commands,
+        mut rng,
+        config,
+        materials,
+        ..
+    } = spawner;
+
+    for death in deaths.iter() {
+        let kind = match enemy_query.get(death.entity) {
+            Ok(Enemy(kind)) => &config.enemies.kinds[*kind],
+            Err(_) => continue,
+        };
+
+        scorer.award(kind.score);
+
+        if let Some(bonus) = kind.drops.roll(&mut *rng) {
+            spawn_bonus(&mut commands, &config, &materials, death.position, bonus);
         }
     }
 }
@@ -0,0 +1,70 @@
+use bevy::{
+    math::IVec2,
+    prelude::{Entity, Vec2, Vec3},
+};
+use std::collections::HashMap;
+
+// -- Resources --
+/// Uniform grid broadphase: entities bucketed by the square cells their
+/// bounds overlap, so a lookup only visits what is nearby.
+///
+/// Kept free of gameplay types, the benchmark builds it on its own.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<Entity>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Empties every cell, keeping their storage for the next rebuild.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    /// Files `entity` under every cell its box, centered on `position`, overlaps.
+    pub fn insert(&mut self, entity: Entity, position: Vec3, size: Vec2) {
+        let (min, max) = self.cell_range(position, size);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                self.cells.entry(IVec2::new(x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    /// Entities sharing a cell with the box, in a stable order. Only a
+    /// candidate list: the caller still has to test the actual overlap.
+    pub fn query(&self, position: Vec3, size: Vec2) -> Vec<Entity> {
+        let (min, max) = self.cell_range(position, size);
+        let mut found = Vec::new();
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if let Some(cell) = self.cells.get(&IVec2::new(x, y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+
+        // Boxes spanning several cells show up once per cell.
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    fn cell_range(&self, position: Vec3, size: Vec2) -> (IVec2, IVec2) {
+        let half = size / 2.0;
+        let center = position.truncate();
+        let cell = |point: Vec2| (point / self.cell_size).floor().as_ivec2();
+
+        (cell(center - half), cell(center + half))
+    }
+}
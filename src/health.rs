@@ -0,0 +1,290 @@
+use crate::{
+    collision::CollisionEvent,
+    despawn_all,
+    powerups::{ActivePowerUps, PowerUp},
+    sim::{SimTime, SIMULATION},
+    GameState, Materials,
+};
+
+use bevy::prelude::*;
+
+/// Ticks something flashes after taking damage it survives.
+const FLASH_DURATION: u64 = 6;
+/// Ticks an explosion takes to grow and fade out.
+const EXPLOSION_DURATION: u64 = 18;
+/// Size an explosion grows to, relative to what blew up.
+const EXPLOSION_GROWTH: f32 = 1.5;
+
+/// Turns projectile hits into damage, and damage into deaths.
+///
+/// Deaths are despawned here; anything reacting to a `DeathEvent` (scoring,
+/// drops, bookkeeping) has to run later in the same tick, while the dead
+/// entity can still be looked up.
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DeathEvent>()
+            .add_system_set_to_stage(
+                SIMULATION,
+                SystemSet::new()
+                    .with_system(deal_damage)
+                    .with_system(apply_damage)
+                    .with_system(damage_flash)
+                    .with_system(explode)
+                    .with_system(explosion_fade),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing).with_system(despawn_all::<Explosion>),
+            );
+    }
+}
+
+// -- Components --
+/// Damage left before death.
+#[derive(Component)]
+pub struct Health {
+    current: u32,
+    max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Fraction of its health left.
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max as f32
+    }
+}
+
+/// Damage dealt by a projectile to whatever it runs into.
+#[derive(Component)]
+pub struct Damage(pub u32);
+
+/// Lets a projectile go through up to `left` targets, hitting each only once,
+/// instead of stopping at the first.
+#[derive(Component)]
+pub struct Piercing {
+    left: u32,
+    hit: Vec<Entity>,
+}
+
+impl Piercing {
+    pub fn new(targets: u32) -> Self {
+        Self {
+            left: targets,
+            hit: Vec::new(),
+        }
+    }
+}
+
+/// Passes the damage it takes on to `target`, multiplied, e.g. a boss's weak point.
+#[derive(Component)]
+pub struct WeakSpot {
+    pub target: Entity,
+    pub multiplier: u32,
+}
+
+/// Tints a damaged entity for a while from the tick it was hit at, then
+/// restores its color.
+#[derive(Component)]
+pub struct Flash {
+    started: u64,
+    color: Color,
+}
+
+/// Blast left behind by a death, growing and fading from the tick it started at.
+#[derive(Component)]
+pub struct Explosion {
+    started: u64,
+}
+
+// -- Events --
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: u32,
+}
+
+/// `entity` ran out of health at `position`. It is despawned at the end of the tick.
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub position: Vec3,
+}
+
+fn deal_damage(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut projectile_query: Query<(&Damage, Option<&mut Piercing>)>,
+    target_query: Query<(Option<&Health>, Option<&WeakSpot>)>,
+) {
+    // Projectile, target, damage and whether it went through a weak spot. A
+    // projectile only ever hits one thing per tick, unless it pierces.
+    let mut hits: Vec<(Entity, Entity, u32, bool)> = Vec::new();
+
+    for event in collisions.iter() {
+        let (Damage(damage), piercing) = match projectile_query.get(event.entity) {
+            Ok(projectile) => projectile,
+            Err(_) => continue,
+        };
+
+        let hit = match target_query.get(event.other) {
+            Ok((_, Some(spot))) => (event.entity, spot.target, damage * spot.multiplier, true),
+            Ok((Some(_), None)) => (event.entity, event.other, *damage, false),
+            _ => continue,
+        };
+
+        let pierces = piercing.is_some();
+
+        match hits.iter_mut().find(|(projectile, target, ..)| {
+            *projectile == event.entity && (!pierces || *target == hit.1)
+        }) {
+            None => hits.push(hit),
+            // Weak spots sit on top of what they belong to, so they get the first say.
+            Some(first) if hit.3 && !first.3 => *first = hit,
+            Some(_) => {}
+        }
+    }
+
+    for (projectile, target, amount, _) in hits {
+        if let Ok((_, Some(mut piercing))) = projectile_query.get_mut(projectile) {
+            // Spent, or already went through this one on an earlier tick.
+            if piercing.left == 0 || piercing.hit.contains(&target) {
+                continue;
+            }
+
+            piercing.hit.push(target);
+            piercing.left -= 1;
+
+            if piercing.left == 0 {
+                commands.entity(projectile).despawn();
+            }
+        }
+
+        damage_events.send(DamageEvent { target, amount });
+    }
+}
+
+/// The one place anything dies.
+fn apply_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+    sim_time: Res<SimTime>,
+    mut query: Query<(
+        &mut Health,
+        &Transform,
+        &Sprite,
+        Option<&Flash>,
+        Option<&mut ActivePowerUps>,
+    )>,
+) {
+    for event in damage_events.iter() {
+        let (mut health, transform, sprite, flash, powerups) = match query.get_mut(event.target) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+
+        // Already dead this tick.
+        if health.current == 0 {
+            continue;
+        }
+
+        // A shield soaks up a whole hit.
+        if let Some(mut powerups) = powerups {
+            if powerups.consume(PowerUp::Shield) {
+                continue;
+            }
+        }
+
+        health.current = health.current.saturating_sub(event.amount);
+
+        if health.current > 0 {
+            commands.entity(event.target).insert(Flash {
+                started: sim_time.ticks(),
+                color: flash.map_or(sprite.color, |flash| flash.color),
+            });
+        } else {
+            commands.entity(event.target).despawn();
+            deaths.send(DeathEvent {
+                entity: event.target,
+                position: transform.translation,
+            });
+        }
+    }
+}
+
+fn damage_flash(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    materials: Res<Materials>,
+    mut query: Query<(Entity, &Flash, &mut Sprite)>,
+) {
+    let now = sim_time.ticks();
+
+    for (entity, flash, mut sprite) in query.iter_mut() {
+        if now >= flash.started + FLASH_DURATION {
+            sprite.color = flash.color;
+            commands.entity(entity).remove::<Flash>();
+        } else {
+            sprite.color = materials.enemy_hit;
+        }
+    }
+}
+
+fn explode(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    sim_time: Res<SimTime>,
+    materials: Res<Materials>,
+    query: Query<&Sprite>,
+) {
+    for death in deaths.iter() {
+        let size = query
+            .get(death.entity)
+            .ok()
+            .and_then(|sprite| sprite.custom_size)
+            .unwrap_or(Vec2::splat(16.0));
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(size),
+                    color: materials.explosion,
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: death.position,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(Explosion {
+                started: sim_time.ticks(),
+            });
+    }
+}
+
+fn explosion_fade(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    mut query: Query<(Entity, &Explosion, &mut Transform, &mut Sprite)>,
+) {
+    let now = sim_time.ticks();
+
+    for (entity, explosion, mut transform, mut sprite) in query.iter_mut() {
+        let elapsed = now - explosion.started;
+
+        if elapsed >= EXPLOSION_DURATION {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let progress = elapsed as f32 / EXPLOSION_DURATION as f32;
+        transform.scale = Vec3::splat(1.0 + (EXPLOSION_GROWTH - 1.0) * progress);
+        sprite.color.set_a(1.0 - progress);
+    }
+}
@@ -1,67 +1,312 @@
+mod bosses;
+mod bunkers;
+mod classic;
+mod cli;
+mod collision;
+mod config;
+mod controls;
 mod enemies;
+mod grid;
+mod health;
+mod menu;
 mod player;
+mod powerups;
+mod replay;
+mod rng;
+mod scores;
+mod sim;
+mod ufo;
 mod ui;
+mod waves;
 
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
 
-use enemies::EnemyPlugin;
-use player::PlayerPlugin;
-use ui::{HighScore, UiPlugin, WinSize};
+use cli::Options;
+use config::{GameConfig, CONFIG_FILE};
+use controls::ControlsPlugin;
+use menu::MenuPlugin;
+use powerups::PowerUp;
+use replay::{Recorder, Replay};
+use rng::GameRng;
+use scores::{HighScore, ScoresPlugin};
+use sim::{InterpolationPlugin, Simulation};
+use ui::{UiPlugin, WinSize};
 
-const TIME_STEP: f32 = 1.0 / 60.0;
-const RESPAWN_DELAY: f64 = 1.0;
+const LAST_REPLAY_FILE: &str = "last-replay.ron";
+
+// -- States --
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    EnterInitials,
+    Controls,
+}
 
 // -- Resources --
 struct Materials {
     player: Color,
     bullet: Color,
     e_bullet: Color,
-    enemy: Color,
-    bonus: Color,
+    enemy_hit: Color,
+    weak_point: Color,
+    laser: Color,
+    laser_warmup: Color,
+    bunker: Color,
+    explosion: Color,
+    rapid_fire: Color,
+    spread_shot: Color,
+    shield: Color,
+    speed_boost: Color,
+    score_multiplier: Color,
+    weapon_up: Color,
+}
+
+impl Default for Materials {
+    fn default() -> Self {
+        Self {
+            player: Color::rgb(0.24, 0.4, 0.8),
+            bullet: Color::rgb(0.6, 0.6, 0.96),
+            e_bullet: Color::rgb(0.8, 0.4, 0.64),
+            enemy_hit: Color::rgb(0.95, 0.95, 0.95),
+            weak_point: Color::rgb(0.95, 0.85, 0.3),
+            laser: Color::rgb(1.0, 0.3, 0.45),
+            laser_warmup: Color::rgba(1.0, 0.3, 0.45, 0.4),
+            bunker: Color::rgb(0.3, 0.75, 0.35),
+            explosion: Color::rgb(1.0, 0.75, 0.3),
+            rapid_fire: Color::rgb(0.9, 0.8, 0.4),
+            spread_shot: Color::rgb(0.4, 0.85, 0.5),
+            shield: Color::rgb(0.4, 0.8, 0.9),
+            speed_boost: Color::rgb(0.95, 0.55, 0.25),
+            score_multiplier: Color::rgb(0.85, 0.5, 0.95),
+            weapon_up: Color::rgb(0.95, 0.35, 0.35),
+        }
+    }
+}
+
+impl Materials {
+    fn bonus(&self, kind: PowerUp) -> Color {
+        match kind {
+            PowerUp::RapidFire => self.rapid_fire,
+            PowerUp::SpreadShot => self.spread_shot,
+            PowerUp::Shield => self.shield,
+            PowerUp::SpeedBoost => self.speed_boost,
+            PowerUp::ScoreMultiplier => self.score_multiplier,
+            PowerUp::WeaponUp => self.weapon_up,
+        }
+    }
 }
 
 // -- Components --
 #[derive(Component, Debug)]
 struct Speed(f32);
-impl Default for Speed {
-    fn default() -> Self {
-        Self(200.0)
+/// Distance moved per second. The ship's is eased toward where the player steers.
+#[derive(Component, Debug)]
+struct Velocity(Vec2);
+
+/// What systems spawning gameplay entities commonly need, as one parameter.
+#[derive(SystemParam)]
+struct Spawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    rng: ResMut<'w, GameRng>,
+    config: Res<'w, GameConfig>,
+    materials: Res<'w, Materials>,
+    win_size: Res<'w, WinSize>,
+}
+
+/// Despawns every entity (and its children) tagged with `T`.
+fn despawn_all<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Per-user directory for saved games data.
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("rust-invaders"))
+        .unwrap_or_default()
+}
+
+/// Where `file` is saved: in the data directory, unless the environment
+/// variable `var` names somewhere else, e.g. to keep tests off the real file.
+fn data_path(file: &str, var: &str) -> PathBuf {
+    match env::var_os(var) {
+        Some(path) => PathBuf::from(path),
+        None => data_dir().join(file),
     }
 }
 
+/// Reads the RON saved at `path`, or `None` if there's none. A corrupt file
+/// is warned about, naming it as `what`, and otherwise treated as missing.
+fn load_ron<T: DeserializeOwned>(path: &Path, what: &str) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring corrupt {} file {}: {}", what, path.display(), err);
+            None
+        }
+    }
+}
+
+/// Saves `value` to `path` as RON, creating its directory if need be.
+fn save_ron<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let contents = ron::ser::to_string_pretty(value, Default::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+    fs::write(path, contents)
+}
+
+/// A path in the temp directory for each test's files. Names must be unique
+/// across modules, so tests can run side by side.
+#[cfg(test)]
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("rust-invaders-{}-{}.ron", process::id(), name))
+}
+
 fn setup(mut commands: Commands, mut windows: ResMut<Windows>) {
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
     commands.spawn_bundle(UiCameraBundle::default());
 
     let window = windows.get_primary_mut().unwrap();
 
-    commands.insert_resource(Materials {
-        player: Color::rgb(0.24, 0.4, 0.8),
-        bullet: Color::rgb(0.6, 0.6, 0.96),
-        e_bullet: Color::rgb(0.8, 0.4, 0.64),
-        enemy: Color::rgb(0.8, 0.2, 0.26),
-        bonus: Color::rgb(0.9, 0.8, 0.4),
-    });
-
     commands.insert_resource(WinSize {
         w: window.width(),
         h: window.height(),
     });
 }
 
+/// Loads the tuning file named on the command line, or the default one if present.
+fn load_config(options: &Options) -> GameConfig {
+    let (path, required) = match &options.config {
+        Some(path) => (path.clone(), true),
+        None => (PathBuf::from(CONFIG_FILE), false),
+    };
+
+    let mut config = GameConfig::load(&path, required).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path.display(), err);
+        process::exit(2);
+    });
+
+    if options.seed.is_some() {
+        config.seed = options.seed;
+    }
+
+    if let Some(mode) = options.mode {
+        config.mode = mode;
+    }
+
+    config
+}
+
+fn run_replay(path: &Path) {
+    let replay = Replay::load(path).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(2);
+    });
+
+    let mut simulation = Simulation::replaying(&replay);
+
+    simulation.run(replay.ticks);
+
+    if simulation.score() == replay.final_score && simulation.ticks() == replay.ticks {
+        println!(
+            "Replay verified: score {} after {} ticks",
+            replay.final_score, replay.ticks
+        );
+    } else {
+        eprintln!(
+            "Replay mismatch: expected score {} after {} ticks, got {} after {}",
+            replay.final_score,
+            replay.ticks,
+            simulation.score(),
+            simulation.ticks()
+        );
+        process::exit(1);
+    }
+}
+
+fn run_headless(options: &Options, mut config: GameConfig) {
+    config.width = options.width.unwrap_or(config.width);
+    config.height = options.height.unwrap_or(config.height);
+
+    // The overrides skipped the validation in `load_config`.
+    if let Err(err) = config.validate() {
+        eprintln!("{}", err);
+        process::exit(2);
+    }
+
+    let mut simulation = Simulation::new(config);
+
+    if let Some(path) = &options.record {
+        simulation = simulation.with_recording(path.clone());
+    }
+
+    simulation.run(options.ticks);
+    simulation.save_recording();
+
+    println!(
+        "seed: {}, ticks: {}, score: {}, game over: {}",
+        simulation.seed(),
+        simulation.ticks(),
+        simulation.score(),
+        simulation.is_over()
+    );
+}
+
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgb(0.2, 0.2, 0.2)))
+    let options = Options::from_args();
+
+    if let Some(path) = &options.replay {
+        run_replay(path);
+        return;
+    }
+
+    let config = load_config(&options);
+
+    if options.headless {
+        run_headless(&options, config);
+        return;
+    }
+
+    let mut app = App::new();
+
+    app.insert_resource(ClearColor(Color::rgb(0.2, 0.2, 0.2)))
         .insert_resource(WindowDescriptor {
             title: "Rust Invaders".to_owned(),
-            width: 480.0,
-            height: 640.0,
+            width: config.width,
+            height: config.height,
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
+        .add_plugin(ControlsPlugin)
+        .add_state(GameState::Title);
+
+    sim::add_gameplay(&mut app, config);
+    app.add_plugin(InterpolationPlugin);
+
+    let record_to = options
+        .record
+        .unwrap_or_else(|| data_dir().join(LAST_REPLAY_FILE));
+
+    app.insert_resource(Recorder::new(Some(record_to)))
+        .add_plugin(ScoresPlugin)
         .add_plugin(UiPlugin)
-        .add_plugin(PlayerPlugin)
-        .add_plugin(EnemyPlugin)
+        .add_plugin(MenuPlugin)
         .add_startup_system(setup)
         .run();
 }
@@ -0,0 +1,510 @@
+use crate::{
+    config::{GameConfig, GameMode},
+    controls::{Action, Binding, Bindings, Controls, ControlsPath},
+    despawn_all,
+    rng::GameRng,
+    scores::{HighScore, HighScoreTable, Initials, ScoreEntry, ScoresPath},
+    ui::UiFont,
+    waves::WaveManager,
+    GameState,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Rebinding::default())
+            .add_system_set(SystemSet::on_enter(GameState::Title).with_system(spawn_title))
+            .add_system_set(SystemSet::on_update(GameState::Title).with_system(title_input))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Title).with_system(despawn_all::<TitleScreen>),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(pause_input))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(spawn_pause))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(resume_input))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused).with_system(despawn_all::<PauseScreen>),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(spawn_game_over))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(game_over_input))
+            .add_system_set(
+                SystemSet::on_exit(GameState::GameOver).with_system(despawn_all::<GameOverScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::EnterInitials).with_system(spawn_initials_entry),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::EnterInitials)
+                    .with_system(initials_input)
+                    .with_system(draw_initials),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::EnterInitials)
+                    .with_system(despawn_all::<InitialsScreen>),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Controls).with_system(spawn_controls))
+            .add_system_set(
+                SystemSet::on_update(GameState::Controls)
+                    .with_system(controls_input)
+                    .with_system(draw_controls),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Controls).with_system(despawn_all::<ControlsScreen>),
+            );
+    }
+}
+
+// -- Components --
+#[derive(Component)]
+pub struct TitleScreen;
+#[derive(Component)]
+pub struct PauseScreen;
+#[derive(Component)]
+pub struct GameOverScreen;
+#[derive(Component)]
+pub struct InitialsScreen;
+#[derive(Component)]
+pub struct InitialsDisplay;
+#[derive(Component)]
+pub struct ModeDisplay;
+#[derive(Component)]
+pub struct ControlsScreen;
+/// Line of the controls screen listing the bindings of `Action::ALL[i]`, or
+/// the reset entry just past the last action.
+#[derive(Component)]
+pub struct BindingRow(usize);
+#[derive(Component)]
+pub struct ControlsHelp;
+
+// -- Resources --
+/// Action picked on the controls screen, and whether it waits for a new input.
+#[derive(Default)]
+pub struct Rebinding {
+    selected: usize,
+    listening: bool,
+}
+
+const INITIALS_LEN: usize = 3;
+/// Entry of the controls screen, after the actions, that restores the default bindings.
+const RESET_ROW: usize = Action::ALL.len();
+
+/// Typing on the initials entry screen.
+#[derive(SystemParam)]
+struct InitialsEntry<'w, 's> {
+    chars: EventReader<'w, 's, ReceivedCharacter>,
+    keyboard_input: Res<'w, Input<KeyCode>>,
+    initials: ResMut<'w, Initials>,
+}
+
+impl<'w, 's> InitialsEntry<'w, 's> {
+    /// Applies this frame's typing, returning the initials entered so far.
+    fn edit(&mut self) -> &str {
+        for event in self.chars.iter() {
+            if event.char.is_ascii_alphanumeric() && self.initials.0.len() < INITIALS_LEN {
+                self.initials.0.push(event.char.to_ascii_uppercase());
+            }
+        }
+
+        // Editing the text sticks to the keyboard, whatever the bindings.
+        if self.keyboard_input.just_pressed(KeyCode::Back) {
+            self.initials.0.pop();
+        }
+
+        &self.initials.0
+    }
+}
+
+/// Spawns a full-window overlay with the given lines stacked in the center,
+/// returning the text entity of each line.
+fn spawn_screen(
+    commands: &mut Commands,
+    font: &UiFont,
+    lines: &[(String, f32, Color)],
+    marker: impl Component,
+) -> Vec<Entity> {
+    let mut texts = Vec::with_capacity(lines.len());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (value, font_size, color) in lines {
+                let text = parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(8.0)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section(
+                        value.clone(),
+                        TextStyle {
+                            font: font.0.clone_weak(),
+                            font_size: *font_size,
+                            color: *color,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                });
+
+                texts.push(text.id());
+            }
+        })
+        .insert(marker);
+
+    texts
+}
+
+fn mode_line(mode: GameMode) -> String {
+    format!("< {} >", mode.name())
+}
+
+fn spawn_title(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    table: Res<HighScoreTable>,
+    config: Res<GameConfig>,
+    controls: Res<Controls>,
+) {
+    let bindings = &controls.bindings;
+    let mut lines = vec![
+        (String::from("Rust Invaders"), 48.0, Color::YELLOW),
+        (mode_line(config.mode), 32.0, Color::WHITE),
+        (
+            format!(
+                "{}/{} to pick a mode",
+                bindings.describe(Action::MoveLeft),
+                bindings.describe(Action::MoveRight)
+            ),
+            20.0,
+            Color::WHITE,
+        ),
+        (
+            format!("Press {} to start", bindings.describe(Action::Confirm)),
+            24.0,
+            Color::WHITE,
+        ),
+        (
+            format!("Press {} for controls", bindings.describe(Action::Back)),
+            20.0,
+            Color::WHITE,
+        ),
+    ];
+
+    if !table.entries().is_empty() {
+        lines.push((String::from("High Scores"), 24.0, Color::YELLOW));
+    }
+
+    for (rank, entry) in table.entries().iter().enumerate() {
+        let line = format!(
+            "{:>2}. {:<3} {:>8}  W{}",
+            rank + 1,
+            entry.initials,
+            entry.score,
+            entry.wave
+        );
+        lines.push((line, 20.0, Color::WHITE));
+    }
+
+    let texts = spawn_screen(&mut commands, &font, &lines, TitleScreen);
+    commands.entity(texts[1]).insert(ModeDisplay);
+}
+
+fn spawn_pause(mut commands: Commands, font: Res<UiFont>, controls: Res<Controls>) {
+    let resume = format!(
+        "Press {} to resume",
+        controls.bindings.describe(Action::Pause)
+    );
+    let lines = [
+        (String::from("Paused"), 48.0, Color::YELLOW),
+        (resume, 24.0, Color::WHITE),
+    ];
+
+    spawn_screen(&mut commands, &font, &lines, PauseScreen);
+}
+
+fn spawn_game_over(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    mut score: ResMut<HighScore>,
+    waves: Res<WaveManager>,
+    rng: Res<GameRng>,
+    controls: Res<Controls>,
+) {
+    let new_best = score.0 > score.1;
+    if new_best {
+        score.1 = score.0;
+    }
+
+    let mut lines = vec![
+        (String::from("Game Over"), 48.0, Color::YELLOW),
+        (format!("Score: {}", score.0), 32.0, Color::WHITE),
+        (format!("Wave {}", waves.number()), 24.0, Color::WHITE),
+    ];
+
+    if new_best {
+        lines.push((String::from("New best!"), 24.0, Color::YELLOW));
+    }
+
+    lines.push((format!("Seed: {}", rng.seed()), 20.0, Color::WHITE));
+    let next = format!(
+        "Press {} to continue",
+        controls.bindings.describe(Action::Confirm)
+    );
+    lines.push((next, 24.0, Color::WHITE));
+
+    spawn_screen(&mut commands, &font, &lines, GameOverScreen);
+}
+
+// Transitions happen within the same frame, so the triggering action is
+// consumed to keep the next state from seeing it as freshly pressed.
+fn title_input(
+    mut controls: ResMut<Controls>,
+    mut state: ResMut<State<GameState>>,
+    mut config: ResMut<GameConfig>,
+    mut query: Query<&mut Text, With<ModeDisplay>>,
+) {
+    let step = if controls.just_pressed(Action::MoveLeft) {
+        GameMode::ALL.len() - 1
+    } else if controls.just_pressed(Action::MoveRight) {
+        1
+    } else {
+        0
+    };
+
+    if step != 0 {
+        let current = GameMode::ALL.iter().position(|mode| *mode == config.mode);
+        let next = (current.unwrap_or(0) + step) % GameMode::ALL.len();
+        config.mode = GameMode::ALL[next];
+
+        for mut text in query.iter_mut() {
+            text.sections[0].value = mode_line(config.mode);
+        }
+    }
+
+    if controls.just_pressed(Action::Confirm) {
+        controls.consume(Action::Confirm);
+        state.set(GameState::Playing).unwrap();
+    } else if controls.just_pressed(Action::Back) {
+        controls.consume(Action::Back);
+        state.set(GameState::Controls).unwrap();
+    }
+}
+
+fn pause_input(mut controls: ResMut<Controls>, mut state: ResMut<State<GameState>>) {
+    if controls.just_pressed(Action::Pause) {
+        controls.consume(Action::Pause);
+        state.push(GameState::Paused).unwrap();
+    }
+}
+
+fn resume_input(mut controls: ResMut<Controls>, mut state: ResMut<State<GameState>>) {
+    if controls.just_pressed(Action::Pause) {
+        controls.consume(Action::Pause);
+        state.pop().unwrap();
+    }
+}
+
+fn game_over_input(
+    mut controls: ResMut<Controls>,
+    mut state: ResMut<State<GameState>>,
+    score: Res<HighScore>,
+    table: Res<HighScoreTable>,
+) {
+    if controls.just_pressed(Action::Confirm) {
+        controls.consume(Action::Confirm);
+
+        let next = if table.qualifies(score.0) {
+            GameState::EnterInitials
+        } else {
+            GameState::Title
+        };
+
+        state.set(next).unwrap();
+    }
+}
+
+fn spawn_initials_entry(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    mut initials: ResMut<Initials>,
+    score: Res<HighScore>,
+    controls: Res<Controls>,
+) {
+    initials.0.clear();
+
+    let confirm = format!(
+        "Press {} to confirm",
+        controls.bindings.describe(Action::Confirm)
+    );
+
+    let lines = [
+        (String::from("New high score!"), 48.0, Color::YELLOW),
+        (format!("Score: {}", score.0), 32.0, Color::WHITE),
+        (String::from("Enter your initials"), 24.0, Color::WHITE),
+        (String::new(), 48.0, Color::YELLOW),
+        (confirm, 24.0, Color::WHITE),
+    ];
+
+    let texts = spawn_screen(&mut commands, &font, &lines, InitialsScreen);
+    commands.entity(texts[3]).insert(InitialsDisplay);
+}
+
+fn initials_input(
+    mut entry: InitialsEntry,
+    mut controls: ResMut<Controls>,
+    mut state: ResMut<State<GameState>>,
+    mut table: ResMut<HighScoreTable>,
+    path: Res<ScoresPath>,
+    score: Res<HighScore>,
+    waves: Res<WaveManager>,
+) {
+    let initials = entry.edit();
+
+    if controls.just_pressed(Action::Confirm) && !initials.is_empty() {
+        controls.consume(Action::Confirm);
+
+        table.insert(ScoreEntry::new(initials, score.0, waves.number()));
+        if let Err(err) = table.save(&path.0) {
+            warn!(
+                "Could not save high scores to {}: {}",
+                path.0.display(),
+                err
+            );
+        }
+
+        state.set(GameState::Title).unwrap();
+    }
+}
+
+fn draw_initials(initials: Res<Initials>, mut query: Query<&mut Text, With<InitialsDisplay>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("{:_<width$}", initials.0, width = INITIALS_LEN);
+    }
+}
+
+fn spawn_controls(mut commands: Commands, font: Res<UiFont>, mut rebinding: ResMut<Rebinding>) {
+    *rebinding = Rebinding::default();
+
+    let mut lines = vec![(String::from("Controls"), 48.0, Color::YELLOW)];
+    lines.extend((0..=RESET_ROW).map(|_| (String::new(), 20.0, Color::WHITE)));
+    lines.push((String::new(), 20.0, Color::WHITE));
+
+    let texts = spawn_screen(&mut commands, &font, &lines, ControlsScreen);
+
+    for (row, text) in texts[1..=RESET_ROW + 1].iter().enumerate() {
+        commands.entity(*text).insert(BindingRow(row));
+    }
+    commands.entity(texts[lines.len() - 1]).insert(ControlsHelp);
+}
+
+/// Picks an action and binds it to the next key, button or stick pushed;
+/// keyboard and gamepad bindings are replaced separately. Escape backs out
+/// of a rebind instead, and the last entry puts back the defaults.
+fn controls_input(
+    mut controls: ResMut<Controls>,
+    mut rebinding: ResMut<Rebinding>,
+    mut state: ResMut<State<GameState>>,
+    path: Res<ControlsPath>,
+) {
+    if rebinding.listening {
+        if let Some(pressed) = controls.latest() {
+            let action = Action::ALL[rebinding.selected];
+            rebinding.listening = false;
+
+            if controls.bindings.rebind_unless_cancelled(action, pressed) {
+                // Keeps the new binding from also acting on this screen.
+                controls.consume(action);
+                save_bindings(&controls.bindings, &path);
+            }
+        }
+        return;
+    }
+
+    let count = RESET_ROW + 1;
+
+    if controls.just_pressed(Action::MoveUp) {
+        rebinding.selected = (rebinding.selected + count - 1) % count;
+    } else if controls.just_pressed(Action::MoveDown) {
+        rebinding.selected = (rebinding.selected + 1) % count;
+    }
+
+    if controls.just_pressed(Action::Confirm) {
+        controls.consume(Action::Confirm);
+
+        if rebinding.selected == RESET_ROW {
+            controls.bindings = Bindings::default();
+            save_bindings(&controls.bindings, &path);
+        } else {
+            rebinding.listening = true;
+        }
+    } else if controls.just_pressed(Action::Back) {
+        controls.consume(Action::Back);
+        state.set(GameState::Title).unwrap();
+    }
+}
+
+fn save_bindings(bindings: &Bindings, path: &ControlsPath) {
+    if let Err(err) = bindings.save(&path.0) {
+        warn!("Could not save controls to {}: {}", path.0.display(), err);
+    }
+}
+
+fn draw_controls(
+    controls: Res<Controls>,
+    rebinding: Res<Rebinding>,
+    mut row_query: Query<(&BindingRow, &mut Text)>,
+    mut help_query: Query<&mut Text, (With<ControlsHelp>, Without<BindingRow>)>,
+) {
+    let bindings = &controls.bindings;
+
+    for (BindingRow(row), mut text) in row_query.iter_mut() {
+        let selected = *row == rebinding.selected;
+
+        let value = match Action::ALL.get(*row) {
+            Some(&action) => {
+                let bound = if selected && rebinding.listening {
+                    String::from("press a key or button, Escape to cancel")
+                } else {
+                    let bound: Vec<String> = bindings
+                        .get(action)
+                        .iter()
+                        .map(Binding::to_string)
+                        .collect();
+                    bound.join(", ")
+                };
+                format!("{}: {}", action.name(), bound)
+            }
+            None => String::from("Reset to defaults"),
+        };
+
+        let section = &mut text.sections[0];
+        section.value = value;
+        section.style.color = if selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+
+    for mut text in help_query.iter_mut() {
+        text.sections[0].value = format!(
+            "{}/{} to pick, {} to rebind, {} to go back",
+            bindings.describe(Action::MoveUp),
+            bindings.describe(Action::MoveDown),
+            bindings.describe(Action::Confirm),
+            bindings.describe(Action::Back)
+        );
+    }
+}
@@ -1,27 +1,45 @@
 use crate::{
-    enemies::{ActiveEnemies, Enemy},
-    HighScore, Materials, Speed, WinSize, RESPAWN_DELAY, TIME_STEP,
+    collision::{Collider, DespawnOnHit, DespawnOutOfBounds, Layer, Shape},
+    config::{GameConfig, ShotPattern},
+    despawn_all,
+    enemies::fan,
+    health::{Damage, DeathEvent, Health, Piercing},
+    powerups::{ActivePowerUps, PowerUp},
+    replay::PlayerInput,
+    sim::{Interpolated, SimTime, SIMULATION},
+    GameState, HighScore, Materials, Speed, Velocity, WinSize,
 };
 
-use bevy::{core::FixedTimestep, prelude::*, sprite::collide_aabb::collide};
-use rand::{thread_rng, Rng};
-use std::collections::HashSet;
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+/// Ticks the ship spends shown, then hidden, while invulnerable.
+const BLINK_INTERVAL: u64 = 6;
+/// Radians homing missiles fan out over when launched together.
+const HOMING_FAN: f32 = 0.8;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerState::default())
-            .add_startup_stage("spawn_player", SystemStage::single(spawn_player))
-            .add_system(player_movement)
-            .add_system(player_fire)
-            .add_system(bullet_movement)
-            .add_system(bullet_hit)
-            .add_system(bonus_drop)
-            .add_system_set(
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_run))
+            .add_system_set_to_stage(
+                SIMULATION,
                 SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(1.0))
-                    .with_system(spawn_player),
+                    .with_system(player_movement)
+                    .with_system(player_fire)
+                    .with_system(homing)
+                    .with_system(bullet_movement)
+                    .with_system(player_death)
+                    .with_system(player_respawn)
+                    .with_system(extra_life)
+                    .with_system(invulnerability_blink),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(despawn_all::<Player>)
+                    .with_system(despawn_all::<Bullet>),
             );
     }
 }
@@ -29,27 +47,81 @@ impl Plugin for PlayerPlugin {
 // -- Components --
 #[derive(Component)]
 pub struct Player;
+/// The weapon carried, at which level, and when it may fire again.
+#[derive(Component)]
+pub struct Weapon {
+    /// Index into `GameConfig::weapons`.
+    def: usize,
+    level: usize,
+    last_shot: Option<u64>,
+    /// Fire was let go of since the last shot, for weapons that don't auto-fire.
+    ready: bool,
+}
+
+impl Weapon {
+    fn new(def: usize) -> Self {
+        Self {
+            def,
+            level: 0,
+            last_shot: None,
+            ready: true,
+        }
+    }
+
+    /// Goes up a level, unless already at the top one.
+    pub fn upgrade(&mut self, config: &GameConfig) {
+        let top = config.weapons[self.def].levels.len() - 1;
+        self.level = (self.level + 1).min(top);
+    }
+}
+/// Can't be hit for `duration` ticks from `started`.
 #[derive(Component)]
-pub struct PlayerReadyFire(bool);
+pub struct Invulnerable {
+    started: u64,
+    duration: u64,
+}
 #[derive(Component)]
 pub struct Bullet;
+/// Turns a missile toward its target at up to `turn_rate` radians per second.
 #[derive(Component)]
-pub struct Bonus;
+pub struct Homing {
+    turn_rate: f32,
+}
 
 // -- Resources --
 pub struct PlayerState {
     alive: bool,
-    last_death: f64,
+    last_death: u64,
+    lives: u32,
+    extra_lives_awarded: usize,
 }
 
 impl PlayerState {
+    pub fn new(lives: u32) -> Self {
+        Self {
+            lives,
+            ..Default::default()
+        }
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
     pub fn spawn(&mut self) {
         self.alive = true;
     }
 
-    pub fn kill_at_time(&mut self, time: f64) {
+    pub fn kill_at_tick(&mut self, tick: u64) {
         self.alive = false;
-        self.last_death = time;
+        self.last_death = tick;
+        self.lives = self.lives.saturating_sub(1);
+    }
+
+    /// Loses every life at once, ending the run.
+    pub fn lose_all_at_tick(&mut self, tick: u64) {
+        self.kill_at_tick(tick);
+        self.lives = 0;
     }
 }
 
@@ -57,194 +129,383 @@ impl Default for PlayerState {
     fn default() -> Self {
         Self {
             alive: false,
-            last_death: 0.0,
+            last_death: 0,
+            lives: 0,
+            extra_lives_awarded: 0,
         }
     }
 }
 
+/// Height the ship starts at, its hitbox resting on the bottom of the
+/// playfield, which is as low as it may go.
+pub fn resting_height(config: &GameConfig, win_size: &WinSize) -> f32 {
+    let shape = Shape::from_config(config.player.hitbox, config.player.size);
+    -win_size.h / 2.0 + shape.bounds().y / 2.0
+}
+
 fn spawn_player(
+    commands: &mut Commands,
+    state: &mut PlayerState,
+    config: &GameConfig,
+    materials: &Materials,
+    win_size: &WinSize,
+) -> Entity {
+    state.spawn();
+
+    let translation = Vec3::new(0.0, resting_height(config, win_size), 10.0);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(config.player.size)),
+                color: materials.player,
+                ..Default::default()
+            },
+
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+
+            ..Default::default()
+        })
+        .insert(Player)
+        .insert(Health::new(config.player.health))
+        .insert(
+            Collider::new(
+                Shape::from_config(config.player.hitbox, config.player.size),
+                Layer::Player,
+            )
+            .hits(&[Layer::Bonus]),
+        )
+        .insert(Interpolated::new(translation))
+        .insert(Speed(config.player.speed))
+        .insert(Velocity(Vec2::ZERO))
+        .insert(Weapon::new(config.starting_weapon()))
+        .insert(ActivePowerUps::default())
+        .id()
+}
+
+fn start_run(
     mut commands: Commands,
     mut state: ResMut<PlayerState>,
     mut score: ResMut<HighScore>,
-    time: Res<Time>,
+    config: Res<GameConfig>,
     materials: Res<Materials>,
     win_size: Res<WinSize>,
 ) {
-    let now = time.seconds_since_startup();
-    let last_death = state.last_death;
+    *state = PlayerState::new(config.player.lives);
+    score.0 = 0;
 
-    if !state.alive && (last_death == 0.0 || now > last_death + RESPAWN_DELAY) {
-        state.spawn();
+    spawn_player(&mut commands, &mut state, &config, &materials, &win_size);
+}
 
-        if score.0 > score.1 {
-            score.1 = score.0;
+fn player_respawn(
+    mut commands: Commands,
+    mut game_state: ResMut<State<GameState>>,
+    mut state: ResMut<PlayerState>,
+    mut sim_time: ResMut<SimTime>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    let now = sim_time.ticks();
+
+    if state.alive || now <= state.last_death + config.player.respawn_delay {
+        return;
+    }
+
+    if state.lives == 0 {
+        sim_time.end_run();
+        game_state.set(GameState::GameOver).unwrap();
+        return;
+    }
+
+    let player = spawn_player(&mut commands, &mut state, &config, &materials, &win_size);
+    commands.entity(player).insert(Invulnerable {
+        started: now,
+        duration: config.player.invulnerability,
+    });
+}
+
+fn extra_life(mut state: ResMut<PlayerState>, score: Res<HighScore>, config: Res<GameConfig>) {
+    while let Some(&threshold) = config.player.extra_life_at.get(state.extra_lives_awarded) {
+        if score.0 < threshold {
+            break;
         }
 
-        score.0 = 0;
+        state.lives += 1;
+        state.extra_lives_awarded += 1;
+    }
+}
 
-        let bottom = -win_size.h / 2.0;
-        commands
-            .spawn_bundle(SpriteBundle {
-                sprite: Sprite {
-                    custom_size: Some(Vec2::new(10.0, 10.0)),
-                    color: materials.player,
-                    ..Default::default()
-                },
+fn invulnerability_blink(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    mut query: Query<(Entity, &Invulnerable, &mut Visibility)>,
+) {
+    let now = sim_time.ticks();
 
-                transform: Transform {
-                    translation: Vec3::new(0.0, bottom + 10.0, 10.0),
-                    ..Default::default()
-                },
+    for (entity, invulnerable, mut visibility) in query.iter_mut() {
+        let elapsed = now - invulnerable.started;
 
-                ..Default::default()
-            })
-            .insert(Player)
-            .insert(Speed::default())
-            .insert(PlayerReadyFire(true));
+        if elapsed >= invulnerable.duration {
+            visibility.is_visible = true;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else {
+            visibility.is_visible = elapsed / BLINK_INTERVAL % 2 == 0;
+        }
     }
 }
 
 fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    window: ResMut<WinSize>,
-    mut query: Query<(&Speed, &mut Transform), With<Player>>,
+    input: Res<PlayerInput>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    mut query: Query<(&Speed, &Collider, &mut Velocity, &mut Transform), With<Player>>,
 ) {
-    if let Ok((speed, mut transform)) = query.get_single_mut() {
-        let cur_x = transform.translation.x;
-        let max_x = window.w / 2.0 - 10.0;
-
-        let dir = if keyboard_input.pressed(KeyCode::A) && cur_x > -max_x {
-            -1.0
-        } else if keyboard_input.pressed(KeyCode::S) && cur_x < max_x {
-            1.0
+    let movement = config.player.movement.get(config.mode);
+    let dt = sim_time.dt();
+
+    if let Ok((speed, collider, mut velocity, mut transform)) = query.get_single_mut() {
+        let mut steer = input.movement();
+        if movement.band == 0.0 {
+            steer.y = 0.0;
+        }
+        // No faster on the diagonal.
+        let target = steer.clamp_length_max(1.0) * speed.0;
+
+        let rate = if target == Vec2::ZERO {
+            movement.deceleration
         } else {
-            0.0
+            movement.acceleration
+        };
+
+        velocity.0 = match rate {
+            Some(rate) => {
+                let change = target - velocity.0;
+                velocity.0 + change.clamp_length_max(rate * dt)
+            }
+            None => target,
         };
 
-        transform.translation.x += dir * speed.0 * TIME_STEP;
+        // Keep the whole hitbox on screen, and within the band.
+        let half = collider.shape.bounds() / 2.0;
+        let bottom = -win_size.h / 2.0;
+        let max_x = win_size.w / 2.0 - half.x;
+        let (min_y, max_y) = (bottom + half.y, bottom + movement.band);
+
+        let translation = &mut transform.translation;
+        *translation += (velocity.0 * dt).extend(0.0);
+
+        if translation.x.abs() > max_x {
+            translation.x = translation.x.clamp(-max_x, max_x);
+            velocity.0.x = 0.0;
+        }
+
+        if movement.band > 0.0 && (translation.y < min_y || translation.y > max_y) {
+            translation.y = translation.y.clamp(min_y, max_y.max(min_y));
+            velocity.0.y = 0.0;
+        }
     }
 }
 
 fn player_fire(
     mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
+    input: Res<PlayerInput>,
+    config: Res<GameConfig>,
     materials: Res<Materials>,
-    mut query: Query<(&Transform, &mut PlayerReadyFire), With<Player>>,
+    sim_time: Res<SimTime>,
+    bullet_query: Query<(), With<Bullet>>,
+    mut query: Query<(&Transform, &mut Weapon, &ActivePowerUps), With<Player>>,
 ) {
-    if let Ok((transform, mut is_ready)) = query.get_single_mut() {
-        if keyboard_input.pressed(KeyCode::Space) && is_ready.0 {
-            is_ready.0 = false;
-
-            let x = transform.translation.x;
-            let y = transform.translation.y;
-
-            commands
-                .spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(2.0, 4.0)),
-                        color: materials.bullet,
-                        ..Default::default()
-                    },
-
-                    transform: Transform {
-                        translation: Vec3::new(x, y, 0.0),
-                        ..Default::default()
-                    },
+    if let Ok((transform, mut weapon, powerups)) = query.get_single_mut() {
+        if !input.fire {
+            weapon.ready = true;
+            return;
+        }
+
+        let now = sim_time.ticks();
+        let def = &config.weapons[weapon.def];
+        let level = &def.levels[weapon.level];
+
+        // Rapid fire shoots twice as often, and keeps shooting while held.
+        let rapid_fire = powerups.is_active(PowerUp::RapidFire);
+        let cooldown = if rapid_fire {
+            level.cooldown / 2
+        } else {
+            level.cooldown
+        };
+        let cooled_down = weapon.last_shot.map_or(true, |last| now >= last + cooldown);
+
+        if !cooled_down || !(weapon.ready || level.auto_fire || rapid_fire) {
+            return;
+        }
+
+        let mut shots = volley(level.pattern);
+
+        if powerups.is_active(PowerUp::SpreadShot) {
+            // One more shot either side, `spread` radians off straight up.
+            let spread = config.powerups.spread;
+            shots.extend(from_center(fan(FRAC_PI_2, 2, 2.0 * spread)));
+        }
 
+        if let Some(max_bullets) = config.player.loadout.get(config.mode).max_bullets {
+            let room = (max_bullets as usize).saturating_sub(bullet_query.iter().count());
+            if room == 0 {
+                return;
+            }
+            shots.truncate(room);
+        }
+
+        weapon.ready = false;
+        weapon.last_shot = Some(now);
+
+        let collider = Collider::new(
+            Shape::from_config(def.bullet_hitbox, def.bullet_size),
+            Layer::PlayerBullet,
+        );
+        let collider = match level.pattern {
+            // A beam passes over cover rather than boring through it.
+            ShotPattern::Laser { .. } => {
+                collider.hits(&[Layer::Enemy, Layer::Boss, Layer::WeakPoint, Layer::Ufo])
+            }
+            _ => collider.hits(&[
+                Layer::Enemy,
+                Layer::Boss,
+                Layer::WeakPoint,
+                Layer::Ufo,
+                Layer::Bunker,
+            ]),
+        };
+
+        for (offset, heading) in shots {
+            let translation = Vec3::new(
+                transform.translation.x + offset,
+                transform.translation.y,
+                0.0,
+            );
+            let velocity = heading * def.bullet_speed;
+
+            let mut bullet = commands.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::from(def.bullet_size)),
+                    color: materials.bullet,
                     ..Default::default()
-                })
+                },
+
+                transform: Transform {
+                    translation,
+                    rotation: facing(velocity),
+                    ..Default::default()
+                },
+
+                ..Default::default()
+            });
+
+            bullet
                 .insert(Bullet)
-                .insert(Speed::default());
+                .insert(collider)
+                .insert(DespawnOutOfBounds {
+                    margin: def.bullet_size.1,
+                })
+                .insert(Damage(level.damage))
+                .insert(Interpolated::new(translation))
+                .insert(Velocity(velocity));
+
+            match level.pattern {
+                ShotPattern::Laser { pierce } => bullet.insert(Piercing::new(pierce)),
+                ShotPattern::Homing { turn_rate, .. } => {
+                    bullet.insert(DespawnOnHit).insert(Homing { turn_rate })
+                }
+                _ => bullet.insert(DespawnOnHit),
+            };
         }
+    }
+}
 
-        if keyboard_input.just_released(KeyCode::Space) {
-            is_ready.0 = true;
-        }
+/// The bullets of one shot, as their offset from the ship's center and their
+/// unit heading.
+fn volley(pattern: ShotPattern) -> Vec<(f32, Vec2)> {
+    match pattern {
+        ShotPattern::Single | ShotPattern::Laser { .. } => vec![(0.0, Vec2::Y)],
+        ShotPattern::Twin { gap } => vec![(-gap / 2.0, Vec2::Y), (gap / 2.0, Vec2::Y)],
+        ShotPattern::Spread { count, spread } => from_center(fan(FRAC_PI_2, count, spread)),
+        ShotPattern::Homing { count, .. } => from_center(fan(FRAC_PI_2, count, HOMING_FAN)),
     }
 }
 
-fn bullet_movement(
-    mut commands: Commands,
-    win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Speed, &mut Transform), With<Bullet>>,
+/// Bullets along `headings`, all fired from the ship's center.
+fn from_center(headings: Vec<Vec2>) -> Vec<(f32, Vec2)> {
+    headings.into_iter().map(|heading| (0.0, heading)).collect()
+}
+
+/// Rotation that points a bullet's sprite along `velocity`.
+fn facing(velocity: Vec2) -> Quat {
+    Quat::from_rotation_z((-velocity.x).atan2(velocity.y))
+}
+
+/// Steers missiles toward the nearest enemy, boss or UFO ahead of them.
+fn homing(
+    sim_time: Res<SimTime>,
+    target_query: Query<(Entity, &Transform, &Collider), Without<Bullet>>,
+    mut query: Query<(&Homing, &mut Velocity, &mut Transform), With<Bullet>>,
 ) {
-    for (entity, speed, mut transform) in query.iter_mut() {
-        transform.translation.y += speed.0 * TIME_STEP;
+    for (homing, mut velocity, mut transform) in query.iter_mut() {
+        let position = transform.translation.truncate();
 
-        if transform.translation.y > win_size.h {
-            commands.entity(entity).despawn();
-        }
+        let nearest = target_query
+            .iter()
+            .filter(|(_, _, collider)| {
+                matches!(collider.layer, Layer::Enemy | Layer::Boss | Layer::Ufo)
+            })
+            .map(|(entity, target, _)| {
+                let offset = target.translation.truncate() - position;
+                (entity, offset)
+            })
+            // Only what is still ahead, so missiles never turn back and circle.
+            .filter(|(_, offset)| offset.y > 0.0)
+            // Ties go to the lower entity, so replays steer the same way.
+            .min_by(|(a, a_offset), (b, b_offset)| {
+                let a_distance = a_offset.length_squared();
+                let b_distance = b_offset.length_squared();
+                a_distance.partial_cmp(&b_distance).unwrap().then(a.cmp(b))
+            });
+
+        let offset = match nearest {
+            Some((_, offset)) => offset,
+            None => continue,
+        };
+
+        let max_turn = homing.turn_rate * sim_time.dt();
+        let turn = velocity.0.angle_between(offset).clamp(-max_turn, max_turn);
+        let (sin, cos) = turn.sin_cos();
+        let v = velocity.0;
+
+        velocity.0 = Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos);
+        transform.rotation = facing(velocity.0);
     }
 }
 
-fn bonus_drop(
-    mut commands: Commands,
-    win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Speed, &mut Transform), With<Bonus>>,
+fn bullet_movement(
+    sim_time: Res<SimTime>,
+    mut query: Query<(&Velocity, &mut Transform), With<Bullet>>,
 ) {
-    for (entity, speed, mut transform) in query.iter_mut() {
-        transform.translation.y -= speed.0 * TIME_STEP;
-
-        if transform.translation.y > win_size.h {
-            commands.entity(entity).despawn();
-        }
+    for (velocity, mut transform) in query.iter_mut() {
+        transform.translation += (velocity.0 * sim_time.dt()).extend(0.0);
     }
 }
 
-fn bullet_hit(
-    mut commands: Commands,
-    bullet_query: Query<(Entity, &Transform, &Sprite), With<Bullet>>,
-    enemy_query: Query<(Entity, &Transform, &Sprite), With<Enemy>>,
-    mut score: ResMut<HighScore>,
-    mut active_enemies: ResMut<ActiveEnemies>,
-    materials: Res<Materials>,
+fn player_death(
+    mut deaths: EventReader<DeathEvent>,
+    mut state: ResMut<PlayerState>,
+    sim_time: Res<SimTime>,
+    query: Query<(), With<Player>>,
 ) {
-    let mut kills: HashSet<Entity> = HashSet::new();
-
-    for (bullet, bullet_tf, bullet_sprite) in bullet_query.iter() {
-        for (enemy, enemy_tf, enemy_sprite) in enemy_query.iter() {
-            let bullet_scale = bullet_tf.scale.abs().truncate();
-            let enemy_scale = enemy_tf.scale.abs().truncate();
-            let mut rng = thread_rng();
-
-            if let Some(_collision) = collide(
-                bullet_tf.translation,
-                bullet_sprite.custom_size.unwrap() * bullet_scale,
-                enemy_tf.translation,
-                enemy_sprite.custom_size.unwrap() * enemy_scale,
-            ) {
-                if kills.get(&enemy).is_none() {
-                    // Despawn colliding sprites
-                    commands.entity(bullet).despawn();
-                    commands.entity(enemy).despawn();
-
-                    score.0 += 100;
-                    active_enemies.0 -= 1;
-
-                    kills.insert(enemy);
-
-                    // Drop bonus
-                    if rng.gen_bool(0.2) {
-                        commands
-                            .spawn_bundle(SpriteBundle {
-                                sprite: Sprite {
-                                    custom_size: Some(Vec2::new(8.0, 8.0)),
-                                    color: materials.bonus,
-                                    ..Default::default()
-                                },
-
-                                transform: Transform {
-                                    translation: Vec3::new(enemy_tf.translation.x, enemy_tf.translation.y, 0.0),
-                                    ..Default::default()
-                                },
-
-                                ..Default::default()
-                            })
-                            .insert(Bonus)
-                            .insert(Speed(100.0));
-                    }
-                }
-            };
+    for death in deaths.iter() {
+        if query.get(death.entity).is_ok() {
+            state.kill_at_tick(sim_time.ticks());
         }
     }
 }
@@ -0,0 +1,201 @@
+use crate::{
+    collision::{Collider, CollisionEvent, DespawnOnHit, DespawnOutOfBounds, Layer, Shape},
+    config::GameConfig,
+    despawn_all,
+    health::Flash,
+    player::{Player, Weapon},
+    sim::{Interpolated, SimTime, SIMULATION},
+    GameState, Materials, Speed,
+};
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            SIMULATION,
+            SystemSet::new()
+                .with_system(bonus_drop)
+                .with_system(bonus_pickup)
+                .with_system(powerup_tick),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(despawn_all::<Bonus>));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PowerUp {
+    RapidFire,
+    SpreadShot,
+    Shield,
+    SpeedBoost,
+    ScoreMultiplier,
+    /// Raises the weapon a level for the rest of the life, rather than for a while.
+    WeaponUp,
+}
+
+impl PowerUp {
+    const ALL: [PowerUp; 6] = [
+        PowerUp::RapidFire,
+        PowerUp::SpreadShot,
+        PowerUp::Shield,
+        PowerUp::SpeedBoost,
+        PowerUp::ScoreMultiplier,
+        PowerUp::WeaponUp,
+    ];
+
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+}
+
+// -- Components --
+#[derive(Component)]
+pub struct Bonus(pub PowerUp);
+
+/// Timed effects currently applied to the player, with the tick each runs out at.
+#[derive(Component, Default)]
+pub struct ActivePowerUps(HashMap<PowerUp, u64>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUp) -> bool {
+        self.0.contains_key(&kind)
+    }
+
+    /// Starts the effect for `duration` ticks from `now`, or starts it over if
+    /// it is already running.
+    pub fn activate(&mut self, kind: PowerUp, now: u64, duration: u64) {
+        self.0.insert(kind, now + duration);
+    }
+
+    /// Ends the effect early, returning whether it was active.
+    pub fn consume(&mut self, kind: PowerUp) -> bool {
+        self.0.remove(&kind).is_some()
+    }
+
+    pub fn score_multiplier(&self, config: &GameConfig) -> u32 {
+        if self.is_active(PowerUp::ScoreMultiplier) {
+            config.powerups.score_multiplier
+        } else {
+            1
+        }
+    }
+}
+
+pub fn spawn_bonus(
+    commands: &mut Commands,
+    config: &GameConfig,
+    materials: &Materials,
+    position: Vec3,
+    kind: PowerUp,
+) {
+    let translation = Vec3::new(position.x, position.y, 0.0);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(config.powerups.size)),
+                color: materials.bonus(kind),
+                ..Default::default()
+            },
+
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+
+            ..Default::default()
+        })
+        .insert(Bonus(kind))
+        .insert(Collider::new(
+            Shape::from_config(config.powerups.hitbox, config.powerups.size),
+            Layer::Bonus,
+        ))
+        .insert(DespawnOnHit)
+        .insert(DespawnOutOfBounds {
+            margin: config.powerups.size.1,
+        })
+        .insert(Interpolated::new(translation))
+        .insert(Speed(config.powerups.fall_speed));
+}
+
+fn bonus_drop(sim_time: Res<SimTime>, mut query: Query<(&Speed, &mut Transform), With<Bonus>>) {
+    for (speed, mut transform) in query.iter_mut() {
+        transform.translation.y -= speed.0 * sim_time.dt();
+    }
+}
+
+fn bonus_pickup(
+    mut events: EventReader<CollisionEvent>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    bonus_query: Query<&Bonus>,
+    mut player_query: Query<(&mut Speed, &mut ActivePowerUps, &mut Weapon), With<Player>>,
+) {
+    for event in events.iter() {
+        if event.layer != Layer::Player || event.other_layer != Layer::Bonus {
+            continue;
+        }
+
+        let (Bonus(kind), (mut speed, mut powerups, mut weapon)) = match (
+            bonus_query.get(event.other),
+            player_query.get_mut(event.entity),
+        ) {
+            (Ok(bonus), Ok(player)) => (bonus, player),
+            _ => continue,
+        };
+
+        let now = sim_time.ticks();
+
+        match kind {
+            PowerUp::WeaponUp => weapon.upgrade(&config),
+            PowerUp::SpeedBoost => {
+                powerups.activate(*kind, now, config.powerups.duration);
+                speed.0 = config.player.speed * config.powerups.speed_boost;
+            }
+            _ => powerups.activate(*kind, now, config.powerups.duration),
+        }
+    }
+}
+
+fn powerup_tick(
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    materials: Res<Materials>,
+    mut query: Query<(&mut ActivePowerUps, &mut Speed, &mut Sprite, Option<&Flash>), With<Player>>,
+) {
+    let now = sim_time.ticks();
+
+    for (mut powerups, mut speed, mut sprite, flash) in query.iter_mut() {
+        let expired: Vec<PowerUp> = powerups
+            .0
+            .iter()
+            .filter_map(|(kind, ends_at)| (now >= *ends_at).then(|| *kind))
+            .collect();
+
+        for kind in expired {
+            powerups.consume(kind);
+
+            // Revert effects that were applied directly to the player.
+            if kind == PowerUp::SpeedBoost {
+                speed.0 = config.player.speed;
+            }
+        }
+
+        // The flash owns the color while it lasts; after that it's only
+        // touched when the shield comes or goes.
+        let color = if powerups.is_active(PowerUp::Shield) {
+            materials.shield
+        } else {
+            materials.player
+        };
+        if flash.is_none() && sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{
+    config::GameConfig,
+    controls::{Action, Controls},
+    rng::GameRng,
+    save_ron,
+    scores::HighScore,
+    sim::{SimTime, SIMULATION},
+    GameState, WinSize,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path, path::PathBuf, vec};
+
+/// Bumped whenever the replay file layout changes.
+const REPLAY_VERSION: u32 = 3;
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Furthest an axis of `PlayerInput` goes either way.
+pub const AXIS_MAX: i8 = i8::MAX;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerInput::default())
+            .insert_resource(InputSource::Live)
+            .insert_resource(Recorder::new(None))
+            // Added ahead of the gameplay systems so each tick sees fresh input.
+            .add_system_to_stage(SIMULATION, capture_input)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_recording))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(save_recording));
+    }
+}
+
+// -- Resources --
+/// Player controls as seen by the gameplay systems for the current tick.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerInput {
+    /// Steering right (positive) or left, up to `AXIS_MAX` with the stick
+    /// all the way over or a key held. Kept whole so replays play back exactly.
+    pub x: i8,
+    /// Steering up (positive) or down.
+    pub y: i8,
+    pub fire: bool,
+}
+
+impl PlayerInput {
+    /// Where the player is steering, each axis from -1 to 1.
+    pub fn movement(self) -> Vec2 {
+        Vec2::new(self.x as f32, self.y as f32) / AXIS_MAX as f32
+    }
+
+    fn to_bits(self) -> u32 {
+        self.x as u8 as u32 | (self.y as u8 as u32) << 8 | (self.fire as u32) << 16
+    }
+
+    fn from_bits(bits: u32) -> Self {
+        Self {
+            x: bits as u8 as i8,
+            y: (bits >> 8) as u8 as i8,
+            fire: bits & (1 << 16) != 0,
+        }
+    }
+}
+
+/// Where `PlayerInput` comes from each tick.
+pub enum InputSource {
+    /// Whatever the player is pressing, through the `Controls`.
+    Live,
+    Playback(vec::IntoIter<PlayerInput>),
+}
+
+/// Collects the input of the current run, saving it at game over.
+pub struct Recorder {
+    path: Option<PathBuf>,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            replay: Replay::default(),
+        }
+    }
+
+    /// Writes the run so far, if recording is enabled.
+    pub fn save(&mut self, seed: u64, win_size: &WinSize, ticks: u64, score: u32) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        self.replay.seed = seed;
+        self.replay.win_size = (win_size.w, win_size.h);
+        self.replay.ticks = ticks;
+        self.replay.final_score = score;
+
+        if let Err(err) = self.replay.save(path) {
+            warn!("Could not save replay to {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub game_version: String,
+    pub seed: u64,
+    pub win_size: (f32, f32),
+    /// Tuning the run was played with.
+    pub config: GameConfig,
+    pub ticks: u64,
+    pub final_score: u32,
+    /// Run-length encoded input, as `(input bits, number of ticks)`.
+    inputs: Vec<(u32, u32)>,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            game_version: GAME_VERSION.to_owned(),
+            seed: 0,
+            win_size: (0.0, 0.0),
+            config: GameConfig::default(),
+            ticks: 0,
+            final_score: 0,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+        let replay: Self = ron::from_str(&contents)
+            .map_err(|err| format!("Invalid replay {}: {}", path.display(), err))?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "Unsupported replay version {} (expected {})",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+
+        // Replays get passed around, so their tuning is checked like a config file's.
+        replay
+            .config
+            .validate()
+            .map_err(|err| format!("Invalid replay {}: {}", path.display(), err))?;
+
+        if replay.game_version != GAME_VERSION {
+            warn!(
+                "Replay was recorded with version {}, this is {}",
+                replay.game_version, GAME_VERSION
+            );
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_ron(path, self)
+    }
+
+    pub fn push(&mut self, input: PlayerInput) {
+        let bits = input.to_bits();
+
+        match self.inputs.last_mut() {
+            Some((last, count)) if *last == bits && *count < u32::MAX => *count += 1,
+            _ => self.inputs.push((bits, 1)),
+        }
+    }
+
+    pub fn playback(&self) -> InputSource {
+        let inputs: Vec<PlayerInput> = self
+            .inputs
+            .iter()
+            .flat_map(|&(bits, count)| {
+                std::iter::repeat(PlayerInput::from_bits(bits)).take(count as usize)
+            })
+            .collect();
+
+        InputSource::Playback(inputs.into_iter())
+    }
+}
+
+fn capture_input(
+    controls: Res<Controls>,
+    mut source: ResMut<InputSource>,
+    mut input: ResMut<PlayerInput>,
+    mut recorder: ResMut<Recorder>,
+) {
+    *input = match &mut *source {
+        InputSource::Live => {
+            // Opposite directions cancel out rather than one winning.
+            let axis = |negative, positive| {
+                let value = controls.strength(positive) - controls.strength(negative);
+                (value * AXIS_MAX as f32).round() as i8
+            };
+
+            PlayerInput {
+                x: axis(Action::MoveLeft, Action::MoveRight),
+                y: axis(Action::MoveDown, Action::MoveUp),
+                fire: controls.pressed(Action::Fire),
+            }
+        }
+        InputSource::Playback(inputs) => inputs.next().unwrap_or_default(),
+    };
+
+    recorder.replay.push(*input);
+}
+
+fn start_recording(mut recorder: ResMut<Recorder>, config: Res<GameConfig>) {
+    recorder.replay = Replay {
+        config: config.clone(),
+        ..Default::default()
+    };
+}
+
+fn save_recording(
+    mut recorder: ResMut<Recorder>,
+    rng: Res<GameRng>,
+    win_size: Res<WinSize>,
+    sim_time: Res<SimTime>,
+    score: Res<HighScore>,
+) {
+    recorder.save(rng.seed(), &win_size, sim_time.ticks(), score.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sim::Simulation, temp_path};
+
+    const TICKS: u64 = 60 * 60;
+
+    /// Weaves about in every direction, firing in bursts, with runs of the
+    /// same input long enough to pack.
+    fn weaving_inputs() -> Vec<PlayerInput> {
+        (0..TICKS)
+            .map(|tick| PlayerInput {
+                x: [-AXIS_MAX, -40, 0, 40, AXIS_MAX][(tick / 45 % 5) as usize],
+                y: [0, AXIS_MAX, 0, -AXIS_MAX][(tick / 70 % 4) as usize],
+                fire: tick % 20 < 12,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn input_survives_packing() {
+        for input in weaving_inputs() {
+            assert_eq!(PlayerInput::from_bits(input.to_bits()), input);
+        }
+    }
+
+    #[test]
+    fn recorded_run_plays_back_the_same() {
+        let path = temp_path("replay-round-trip");
+        let inputs = weaving_inputs();
+        let config = GameConfig {
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let mut simulation = Simulation::new(config)
+            .with_input(InputSource::Playback(inputs.clone().into_iter()))
+            .with_recording(path.clone());
+        simulation.run(TICKS);
+        simulation.save_recording();
+
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(replay.seed, 42);
+        assert_eq!(replay.ticks, simulation.ticks());
+        assert_eq!(replay.final_score, simulation.score());
+        assert!(replay.inputs.len() < inputs.len() / 10);
+
+        let played = match replay.playback() {
+            InputSource::Playback(played) => played.collect::<Vec<_>>(),
+            InputSource::Live => unreachable!(),
+        };
+        assert_eq!(played, inputs[..played.len()]);
+
+        let mut playback = Simulation::replaying(&replay);
+        playback.run(replay.ticks);
+
+        assert!(replay.final_score > 0);
+        assert_eq!(playback.ticks(), replay.ticks);
+        assert_eq!(playback.score(), replay.final_score);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let path = temp_path("replay-old-version");
+        let replay = Replay {
+            version: REPLAY_VERSION - 1,
+            ..Default::default()
+        };
+        replay.save(&path).unwrap();
+
+        let result = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap_err();
+        assert!(err.contains("Unsupported replay version"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let path = temp_path("replay-invalid-config");
+        let mut replay = Replay::default();
+        replay.config.player.loadout.arcade.weapon = String::from("nope");
+        replay.save(&path).unwrap();
+
+        let result = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap_err();
+        assert!(err.contains("player.loadout.arcade.weapon"), "{}", err);
+    }
+
+    #[test]
+    fn corrupt_replay_is_an_error() {
+        let path = temp_path("replay-corrupt");
+        fs::write(&path, "(version: 3, seed:").unwrap();
+
+        let result = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap_err().starts_with("Invalid replay"));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::GameState;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct RngPlugin {
+    /// Seed used for every run; a fresh one is picked per run when unset.
+    pub seed: Option<u64>,
+}
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRng::new(self.seed))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reseed));
+    }
+}
+
+// -- Resources --
+/// Source of all gameplay randomness, so a run can be reproduced from its seed.
+pub struct GameRng {
+    fixed_seed: Option<u64>,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(time_seed);
+
+        Self {
+            fixed_seed,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+fn reseed(mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(rng.fixed_seed);
+}
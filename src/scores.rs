@@ -0,0 +1,234 @@
+use crate::{
+    config::GameConfig, data_path, load_ron, player::Player, powerups::ActivePowerUps, save_ron,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAX_ENTRIES: usize = 10;
+const SCORES_FILE: &str = "scores.ron";
+/// Overrides where the table is stored.
+const SCORES_PATH_VAR: &str = "RUST_INVADERS_SCORES";
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        let path = data_path(SCORES_FILE, SCORES_PATH_VAR);
+        let table = HighScoreTable::load(&path);
+
+        app.insert_resource(HighScore(0, table.best()))
+            .insert_resource(ScoresPath(path))
+            .insert_resource(table)
+            .insert_resource(Initials::default());
+    }
+}
+
+// -- Resources --
+/// Score of the current run and the best score so far.
+pub struct HighScore(pub u32, pub u32);
+
+pub struct ScoresPath(pub PathBuf);
+
+/// Adds points to the current run, scaled by the player's score multiplier.
+#[derive(SystemParam)]
+pub struct Scorer<'w, 's> {
+    score: ResMut<'w, HighScore>,
+    config: Res<'w, GameConfig>,
+    player_query: Query<'w, 's, &'static ActivePowerUps, With<Player>>,
+}
+
+impl<'w, 's> Scorer<'w, 's> {
+    /// Awards `points`, returning how many were actually scored.
+    pub fn award(&mut self, points: u32) -> u32 {
+        let multiplier = self
+            .player_query
+            .get_single()
+            .map_or(1, |powerups| powerups.score_multiplier(&self.config));
+        let points = points * multiplier;
+
+        self.score.0 += points;
+        points
+    }
+}
+
+/// Initials typed in on the entry screen.
+#[derive(Default)]
+pub struct Initials(pub String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub initials: String,
+    pub score: u32,
+    pub wave: u32,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl ScoreEntry {
+    pub fn new(initials: &str, score: u32, wave: u32) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        Self {
+            initials: initials.to_owned(),
+            score,
+            wave,
+            timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScoreTable {
+    /// Loads the table, falling back to an empty one if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        let mut table: Self = load_ron(path, "high score").unwrap_or_default();
+        table.sort();
+        table
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        save_ron(path, self)
+    }
+
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self
+                    .entries
+                    .last()
+                    .map_or(true, |entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, entry: ScoreEntry) {
+        self.entries.push(entry);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        // Stable, so older entries stay ahead of later ties.
+        self.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_path;
+    use std::env;
+
+    fn entry(initials: &str, score: u32) -> ScoreEntry {
+        ScoreEntry::new(initials, score, 1)
+    }
+
+    /// Ten entries, scoring 1000 down to 100.
+    fn full_table() -> HighScoreTable {
+        let mut table = HighScoreTable::default();
+        for i in 1..=MAX_ENTRIES as u32 {
+            table.insert(entry("AAA", i * 100));
+        }
+        table
+    }
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let table = HighScoreTable::load(&temp_path("scores-missing"));
+
+        assert!(table.entries().is_empty());
+        assert_eq!(table.best(), 0);
+    }
+
+    #[test]
+    fn corrupt_file_loads_empty() {
+        let path = temp_path("scores-corrupt");
+        fs::write(&path, "(entries: [(initials: \"AB").unwrap();
+
+        let table = HighScoreTable::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(table.entries().is_empty());
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("scores-round-trip");
+        let mut table = HighScoreTable::default();
+        table.insert(entry("ABC", 500));
+        table.insert(entry("XYZ", 1500));
+
+        table.save(&path).unwrap();
+        let loaded = HighScoreTable::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let scores: Vec<(&str, u32)> = loaded
+            .entries()
+            .iter()
+            .map(|entry| (entry.initials.as_str(), entry.score))
+            .collect();
+        assert_eq!(scores, [("XYZ", 1500), ("ABC", 500)]);
+    }
+
+    #[test]
+    fn keeps_only_the_best_ten() {
+        let mut table = full_table();
+        table.insert(entry("NEW", 150));
+
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert_eq!(table.entries().last().unwrap().score, 150);
+        assert_eq!(table.best(), 1000);
+    }
+
+    #[test]
+    fn tying_the_lowest_of_a_full_table_does_not_qualify() {
+        let table = full_table();
+
+        assert!(!table.qualifies(100));
+        assert!(table.qualifies(101));
+        assert!(HighScoreTable::default().qualifies(1));
+        assert!(!HighScoreTable::default().qualifies(0));
+    }
+
+    #[test]
+    fn earlier_entries_stay_ahead_of_ties() {
+        let mut table = HighScoreTable::default();
+        table.insert(entry("OLD", 300));
+        table.insert(entry("LOW", 100));
+        table.insert(entry("NEW", 300));
+
+        let initials: Vec<&str> = table
+            .entries()
+            .iter()
+            .map(|entry| entry.initials.as_str())
+            .collect();
+        assert_eq!(initials, ["OLD", "NEW", "LOW"]);
+    }
+
+    #[test]
+    fn path_can_be_overridden() {
+        let path = temp_path("scores-override");
+        env::set_var(SCORES_PATH_VAR, &path);
+
+        assert_eq!(data_path(SCORES_FILE, SCORES_PATH_VAR), path);
+        env::remove_var(SCORES_PATH_VAR);
+    }
+}
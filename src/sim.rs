@@ -0,0 +1,510 @@
+use crate::{
+    bosses::BossPlugin,
+    bunkers::BunkerPlugin,
+    classic::ClassicPlugin,
+    collision::CollisionPlugin,
+    config::GameConfig,
+    controls::Controls,
+    enemies::EnemyPlugin,
+    health::HealthPlugin,
+    player::PlayerPlugin,
+    powerups::PowerUpPlugin,
+    replay::{InputSource, Recorder, Replay, ReplayPlugin},
+    rng::{GameRng, RngPlugin},
+    scores::HighScore,
+    ufo::UfoPlugin,
+    waves::WavePlugin,
+    GameState, Materials, WinSize,
+};
+
+use bevy::{ecs::schedule::ShouldRun, input::InputPlugin, prelude::*, transform::TransformSystem};
+use std::path::PathBuf;
+
+/// Stage holding every gameplay system; runs once per fixed tick while playing.
+///
+/// Single threaded so systems always run in insertion order, which keeps a
+/// seeded run reproducible.
+pub const SIMULATION: &str = "simulation";
+
+/// Most ticks a single frame may catch up on, so a long hitch doesn't stall
+/// the game with a burst of catch-up ticks.
+const MAX_CATCH_UP_TICKS: f32 = 5.0;
+
+pub struct SimulationPlugin {
+    /// Seconds per tick.
+    pub time_step: f32,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimTime::new(self.time_step))
+            .add_system_to_stage(CoreStage::PreUpdate, accumulate_time)
+            .add_stage_after(
+                CoreStage::Update,
+                SIMULATION,
+                SystemStage::single_threaded().with_run_criteria(run_simulation),
+            )
+            // Added ahead of the gameplay systems so it sees the previous tick.
+            .add_system_to_stage(SIMULATION, snapshot_positions)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_sim_time));
+    }
+}
+
+/// Smooths rendering by drawing moving sprites between their last two ticks.
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, restore_positions)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_positions.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// -- Components --
+/// Simulated position of the entity at the previous and the latest tick.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl Interpolated {
+    pub fn new(translation: Vec3) -> Self {
+        Self {
+            previous: translation,
+            current: translation,
+        }
+    }
+
+    /// Jumps straight to `translation` instead of sliding there, e.g. when wrapping around.
+    pub fn snap(&mut self, translation: Vec3) {
+        self.previous = translation;
+    }
+}
+
+// -- Resources --
+/// Gameplay clock counting fixed-length ticks, fed by wall time through an accumulator.
+pub struct SimTime {
+    ticks: u64,
+    step: f32,
+    accumulator: f32,
+    /// Set once the run is decided, so no further ticks run before the state changes.
+    ended: bool,
+    /// Ticks are fed by `Simulation::step` instead of wall time.
+    manual: bool,
+}
+
+impl SimTime {
+    pub fn new(step: f32) -> Self {
+        Self {
+            ticks: 0,
+            step,
+            accumulator: 0.0,
+            ended: false,
+            manual: false,
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Seconds per tick.
+    pub fn dt(&self) -> f32 {
+        self.step
+    }
+
+    /// How far rendering is between the last tick and the next, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+
+    pub fn end_run(&mut self) {
+        self.ended = true;
+    }
+}
+
+fn accumulate_time(time: Res<Time>, state: Res<State<GameState>>, mut sim_time: ResMut<SimTime>) {
+    if sim_time.manual || *state.current() != GameState::Playing {
+        return;
+    }
+
+    let max = sim_time.step * MAX_CATCH_UP_TICKS;
+    sim_time.accumulator = (sim_time.accumulator + time.delta_seconds()).min(max);
+}
+
+fn run_simulation(state: Res<State<GameState>>, mut sim_time: ResMut<SimTime>) -> ShouldRun {
+    let playing = *state.current() == GameState::Playing && !sim_time.ended;
+
+    if playing && sim_time.accumulator >= sim_time.step {
+        sim_time.accumulator -= sim_time.step;
+        sim_time.ticks += 1;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+fn reset_sim_time(mut sim_time: ResMut<SimTime>) {
+    // The accumulator is kept, it holds less than a tick of time by now.
+    sim_time.ticks = 0;
+    sim_time.ended = false;
+}
+
+fn restore_positions(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in query.iter_mut() {
+        transform.translation = interpolated.current;
+    }
+}
+
+fn snapshot_positions(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous = transform.translation;
+    }
+}
+
+fn interpolate_positions(
+    sim_time: Res<SimTime>,
+    mut query: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    let alpha = sim_time.alpha();
+
+    for (mut transform, mut interpolated) in query.iter_mut() {
+        interpolated.current = transform.translation;
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+    }
+}
+
+/// Adds the window-independent gameplay plugins.
+pub fn add_gameplay(app: &mut App, config: GameConfig) {
+    let time_step = config.time_step;
+    let seed = config.seed;
+
+    app.insert_resource(Materials::default())
+        .insert_resource(config)
+        .add_plugin(SimulationPlugin { time_step })
+        .add_plugin(RngPlugin { seed })
+        .add_plugin(ReplayPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(HealthPlugin)
+        .add_plugin(PlayerPlugin)
+        .add_plugin(EnemyPlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(ClassicPlugin)
+        .add_plugin(BunkerPlugin)
+        .add_plugin(UfoPlugin)
+        .add_plugin(PowerUpPlugin);
+}
+
+/// Runs the gameplay without a window, renderer or UI, one tick per `step`.
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// Sets up a run on a playfield of the configured window size.
+    pub fn new(config: GameConfig) -> Self {
+        let mut app = App::new();
+        let win_size = WinSize {
+            w: config.width,
+            h: config.height,
+        };
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(InputPlugin)
+            // Nothing is ever pressed; input comes from the `InputSource`.
+            .insert_resource(Controls::default())
+            .insert_resource(win_size)
+            .insert_resource(HighScore(0, 0))
+            .add_state(GameState::Playing);
+
+        add_gameplay(&mut app, config);
+        app.world.get_resource_mut::<SimTime>().unwrap().manual = true;
+
+        Self { app }
+    }
+
+    /// Sets up to play `replay` back with the tuning, seed and playfield it was
+    /// recorded with.
+    pub fn replaying(replay: &Replay) -> Self {
+        let (width, height) = replay.win_size;
+        let config = GameConfig {
+            seed: Some(replay.seed),
+            width,
+            height,
+            ..replay.config.clone()
+        };
+
+        Self::new(config).with_input(replay.playback())
+    }
+
+    /// Feeds the player input from `source` instead of the keyboard.
+    pub fn with_input(mut self, source: InputSource) -> Self {
+        self.app.insert_resource(source);
+        self
+    }
+
+    /// Records the run, saving it to `path` at game over or on `save_recording`.
+    pub fn with_recording(mut self, path: PathBuf) -> Self {
+        self.app.insert_resource(Recorder::new(Some(path)));
+        self
+    }
+
+    pub fn save_recording(&mut self) {
+        let world = &mut self.app.world;
+        let seed = world.get_resource::<GameRng>().unwrap().seed();
+        let ticks = world.get_resource::<SimTime>().unwrap().ticks();
+        let score = world.get_resource::<HighScore>().unwrap().0;
+        let win_size = *world.get_resource::<WinSize>().unwrap();
+
+        let mut recorder = world.get_resource_mut::<Recorder>().unwrap();
+        recorder.save(seed, &win_size, ticks, score);
+    }
+
+    /// Advances the gameplay by one tick.
+    pub fn step(&mut self) {
+        let world = &mut self.app.world;
+        let mut sim_time = world.get_resource_mut::<SimTime>().unwrap();
+        sim_time.accumulator += sim_time.step;
+
+        self.app.update();
+    }
+
+    /// Steps up to `ticks` times, stopping early once the run is over.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            if self.is_over() {
+                break;
+            }
+
+            self.step();
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        *self
+            .app
+            .world
+            .get_resource::<State<GameState>>()
+            .unwrap()
+            .current()
+            == GameState::GameOver
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.app.world.get_resource::<SimTime>().unwrap().ticks()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.app.world.get_resource::<GameRng>().unwrap().seed()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.app.world.get_resource::<HighScore>().unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enemies::{ActiveEnemies, Enemy},
+        player::{Player, PlayerState},
+        replay::{PlayerInput, AXIS_MAX},
+        waves::WaveManager,
+    };
+
+    /// Ten minutes of play.
+    const TICKS: u64 = 60 * 60 * 10;
+    /// More than a busy wave ever has on screen at once.
+    const MAX_ENTITIES: u32 = 400;
+
+    /// Sweeps from side to side, tapping fire, to shoot plenty and miss most drops.
+    fn sweeping_input() -> InputSource {
+        let inputs: Vec<PlayerInput> = (0..TICKS)
+            .map(|tick| PlayerInput {
+                x: if tick / 90 % 2 == 0 {
+                    -AXIS_MAX
+                } else {
+                    AXIS_MAX
+                },
+                y: 0,
+                fire: tick % 8 < 4,
+            })
+            .collect();
+
+        InputSource::Playback(inputs.into_iter())
+    }
+
+    /// Sits still in the middle without firing.
+    fn idle_input(ticks: u64) -> InputSource {
+        let inputs = vec![PlayerInput::default(); ticks as usize];
+        InputSource::Playback(inputs.into_iter())
+    }
+
+    #[test]
+    fn diving_into_the_player_costs_a_life() {
+        let mut config = GameConfig {
+            seed: Some(1),
+            ..Default::default()
+        };
+        // Every kind dives and none shoot, so only running into the ship can hurt it.
+        for kind in &mut config.enemies.kinds {
+            kind.path = String::from("dive");
+            kind.fire = None;
+        }
+        let lives = config.player.lives;
+        let ticks = 60 * 30;
+
+        let mut simulation = Simulation::new(config).with_input(idle_input(ticks));
+        let lives_left = |simulation: &Simulation| {
+            let state = simulation.app.world.get_resource::<PlayerState>().unwrap();
+            state.lives()
+        };
+
+        for _ in 0..ticks {
+            simulation.step();
+
+            if lives_left(&simulation) < lives {
+                break;
+            }
+        }
+
+        assert_eq!(lives_left(&simulation), lives - 1);
+    }
+
+    /// Score, number of entities and where the player is, every 100 ticks.
+    fn trace(seed: u64, ticks: u64) -> Vec<(u32, u32, Option<Vec3>)> {
+        let config = GameConfig {
+            seed: Some(seed),
+            ..Default::default()
+        };
+        let mut simulation = Simulation::new(config).with_input(sweeping_input());
+        let mut trace = Vec::new();
+
+        for tick in 0..ticks {
+            simulation.step();
+
+            if tick % 100 == 0 {
+                let score = simulation.score();
+                let world = &mut simulation.app.world;
+                let player = world
+                    .query_filtered::<&Transform, With<Player>>()
+                    .iter(world)
+                    .next()
+                    .map(|transform| transform.translation);
+
+                trace.push((score, world.entities().len(), player));
+            }
+        }
+
+        trace
+    }
+
+    #[test]
+    fn same_seed_plays_the_same() {
+        assert_eq!(trace(7, 3000), trace(7, 3000));
+    }
+
+    #[test]
+    fn seed_changes_the_run() {
+        assert_ne!(trace(7, 3000), trace(8, 3000));
+    }
+
+    impl Simulation {
+        fn set_state(&mut self, state: GameState) {
+            let mut current = self
+                .app
+                .world
+                .get_resource_mut::<State<GameState>>()
+                .unwrap();
+            current.set(state).unwrap();
+            self.app.update();
+        }
+
+        /// Steps `ticks` times, checking that `ActiveEnemies` keeps up with the
+        /// enemies in the world.
+        fn run_counting_enemies(&mut self, ticks: u64) {
+            for _ in 0..ticks {
+                self.step();
+
+                let world = &mut self.app.world;
+                let enemies = world
+                    .query_filtered::<(), With<Enemy>>()
+                    .iter(world)
+                    .count();
+                let counted = world.get_resource::<ActiveEnemies>().unwrap().count();
+                assert_eq!(counted as usize, enemies, "after {} ticks", self.ticks());
+            }
+        }
+    }
+
+    #[test]
+    fn active_enemies_follow_spawns_deaths_and_restarts() {
+        let mut config = GameConfig {
+            seed: Some(3),
+            ..Default::default()
+        };
+        config.player.lives = u32::MAX / 2;
+
+        let mut simulation = Simulation::new(config).with_input(sweeping_input());
+        simulation.run_counting_enemies(TICKS / 4);
+
+        let wave = simulation
+            .app
+            .world
+            .get_resource::<WaveManager>()
+            .unwrap()
+            .number();
+        assert!(wave > 1, "still on wave {}", wave);
+
+        // Leaving the game despawns every enemy at once, and a new run starts
+        // from an empty playfield.
+        simulation.set_state(GameState::GameOver);
+        assert_eq!(
+            simulation
+                .app
+                .world
+                .get_resource::<ActiveEnemies>()
+                .unwrap()
+                .count(),
+            0
+        );
+
+        simulation.set_state(GameState::Playing);
+        simulation.run_counting_enemies(TICKS / 4);
+    }
+
+    #[test]
+    fn entity_count_stays_bounded() {
+        let mut config = GameConfig {
+            seed: Some(1),
+            ..Default::default()
+        };
+        // Keep the run going, and leave a bonus behind every kill.
+        config.player.lives = u32::MAX / 2;
+        for kind in &mut config.enemies.kinds {
+            kind.drops.chance = 1.0;
+        }
+
+        let mut simulation = Simulation::new(config).with_input(sweeping_input());
+
+        for _ in 0..TICKS {
+            simulation.step();
+
+            let entities = simulation.app.world.entities().len();
+            assert!(
+                entities <= MAX_ENTITIES,
+                "{} entities after {} ticks",
+                entities,
+                simulation.ticks()
+            );
+        }
+
+        assert!(!simulation.is_over());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    collision::{Collider, Layer, Shape},
+    config::GameConfig,
+    despawn_all,
+    health::{DeathEvent, Health},
+    powerups::spawn_bonus,
+    rng::GameRng,
+    scores::Scorer,
+    sim::{Interpolated, SimTime, SIMULATION},
+    GameState, Spawner, Speed, WinSize,
+};
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+/// Ticks the score of a downed UFO stays up.
+const FLOATING_SCORE_DURATION: u64 = 60;
+/// Speed the score rises at while shown.
+const FLOATING_SCORE_RISE: f32 = 20.0;
+
+/// The mystery ship, crossing the top of the playfield on a timer of its own.
+pub struct UfoPlugin;
+
+impl Plugin for UfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UfoTimer::default())
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_ufo_timer))
+            .add_system_set_to_stage(
+                SIMULATION,
+                SystemSet::new()
+                    .with_system(spawn_ufo)
+                    .with_system(ufo_movement)
+                    .with_system(ufo_death)
+                    .with_system(floating_score),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Playing)
+                    .with_system(despawn_all::<Ufo>)
+                    .with_system(despawn_all::<FloatingScore>),
+            );
+    }
+}
+
+// -- Components --
+/// The mystery ship, flying left (-1) or right (1).
+#[derive(Component)]
+pub struct Ufo(f32);
+/// Points scored, shown where they were won for a while from the tick they were.
+#[derive(Component)]
+pub struct FloatingScore {
+    pub points: u32,
+    started: u64,
+}
+
+// -- Resources --
+/// Tick of the next pass, put off for as long as a UFO is around.
+#[derive(Default)]
+pub struct UfoTimer {
+    next: u64,
+}
+
+fn reset_ufo_timer(mut timer: ResMut<UfoTimer>, config: Res<GameConfig>) {
+    timer.next = config.ufo.interval;
+}
+
+fn spawn_ufo(
+    mut commands: Commands,
+    mut timer: ResMut<UfoTimer>,
+    mut rng: ResMut<GameRng>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    query: Query<(), With<Ufo>>,
+) {
+    let now = sim_time.ticks();
+
+    // The interval only counts down while the skies are clear.
+    if !query.is_empty() {
+        timer.next = now + config.ufo.interval;
+    }
+    if now < timer.next {
+        return;
+    }
+
+    let ufo = &config.ufo;
+    let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let x = -direction * (win_size.w + ufo.size.0) / 2.0;
+    let y = win_size.h / 2.0 - ufo.top;
+    let translation = Vec3::new(x, y, 10.0);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::from(ufo.size)),
+                color: ufo.color(),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Ufo(direction))
+        .insert(Health::new(1))
+        .insert(Collider::new(
+            Shape::from_config(ufo.hitbox, ufo.size),
+            Layer::Ufo,
+        ))
+        .insert(Interpolated::new(translation))
+        .insert(Speed(ufo.speed));
+}
+
+fn ufo_movement(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    mut query: Query<(Entity, &Ufo, &Speed, &mut Transform)>,
+) {
+    let max_x = (win_size.w + config.ufo.size.0) / 2.0;
+
+    for (entity, Ufo(direction), speed, mut transform) in query.iter_mut() {
+        transform.translation.x += direction * speed.0 * sim_time.dt();
+
+        if transform.translation.x * direction > max_x {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Scores a downed UFO, shows what it was worth and rolls its drop.
+fn ufo_death(
+    mut deaths: EventReader<DeathEvent>,
+    sim_time: Res<SimTime>,
+    spawner: Spawner,
+    mut scorer: Scorer,
+    ufo_query: Query<(), With<Ufo>>,
+) {
+    let Spawner {
+        mut commands,
+        mut rng,
+        config,
+        materials,
+        ..
+    } = spawner;
+
+    for death in deaths.iter() {
+        if ufo_query.get(death.entity).is_err() {
+            continue;
+        }
+
+        let points = scorer.award(*config.ufo.points.choose(&mut *rng).unwrap());
+
+        commands
+            .spawn()
+            .insert(Transform::from_translation(death.position))
+            .insert(FloatingScore {
+                points,
+                started: sim_time.ticks(),
+            });
+
+        if let Some(bonus) = config.ufo.drops.roll(&mut *rng) {
+            spawn_bonus(&mut commands, &config, &materials, death.position, bonus);
+        }
+    }
+}
+
+fn floating_score(
+    mut commands: Commands,
+    sim_time: Res<SimTime>,
+    mut query: Query<(Entity, &FloatingScore, &mut Transform)>,
+) {
+    let now = sim_time.ticks();
+
+    for (entity, floating, mut transform) in query.iter_mut() {
+        transform.translation.y += FLOATING_SCORE_RISE * sim_time.dt();
+
+        if now >= floating.started + FLOATING_SCORE_DURATION {
+            commands.entity(entity).despawn();
+        }
+    }
+}
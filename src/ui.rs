@@ -1,3 +1,8 @@
+use crate::{
+    bosses::Boss, health::Health, player::PlayerState, scores::HighScore, sim::SimTime,
+    ufo::FloatingScore, waves::WaveManager, GameState,
+};
+
 use bevy::prelude::*;
 
 pub struct UiPlugin;
@@ -5,6 +10,10 @@ pub struct UiPlugin;
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(draw_score)
+            .add_system(draw_lives)
+            .add_system(draw_wave_banner)
+            .add_system(draw_boss_health)
+            .add_system(label_floating_scores)
             .add_startup_stage("setup_ui", SystemStage::single(setup_ui));
     }
 }
@@ -18,9 +27,20 @@ pub struct HighScoreDisplay;
 #[derive(Component)]
 pub struct ScoreDisplay;
 #[derive(Component)]
-pub struct HighScore(pub u32, pub u32);
+pub struct LivesDisplay;
+/// "Wave N" shown in the break before each wave.
+#[derive(Component)]
+pub struct WaveBanner;
+/// Frame of the boss health bar, shown while a boss is around.
+#[derive(Component)]
+pub struct BossHealthBar;
+#[derive(Component)]
+pub struct BossHealthFill;
 
 // -- Resources --
+pub struct UiFont(pub Handle<Font>);
+
+#[derive(Clone, Copy)]
 pub struct WinSize {
     #[allow(unused)]
     pub w: f32,
@@ -97,8 +117,7 @@ fn setup_ui(mut commands: Commands, assets: Res<AssetServer>) {
                     TextSection {
                         value: 0.to_string(),
                         style: TextStyle {
-                            // Last use of the handle, transfer ownership.
-                            font: iosevka,
+                            font: iosevka.clone_weak(),
                             font_size,
                             color: Color::WHITE,
                         },
@@ -110,7 +129,122 @@ fn setup_ui(mut commands: Commands, assets: Res<AssetServer>) {
         })
         .insert(HighScoreDisplay);
 
-    commands.insert_resource(HighScore(0, 0));
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.0),
+                    left: Val::Px(15.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: String::from("Lives: "),
+                        style: TextStyle {
+                            font: iosevka.clone_weak(),
+                            font_size,
+                            color: Color::YELLOW,
+                        },
+                    },
+                    TextSection {
+                        value: 0.to_string(),
+                        style: TextStyle {
+                            font: iosevka.clone_weak(),
+                            font_size,
+                            color: Color::WHITE,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LivesDisplay);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: String::new(),
+                                style: TextStyle {
+                                    font: iosevka.clone_weak(),
+                                    font_size: 48.0,
+                                    color: Color::YELLOW,
+                                },
+                            },
+                            TextSection {
+                                value: String::new(),
+                                style: TextStyle {
+                                    font: iosevka.clone_weak(),
+                                    font_size: 24.0,
+                                    color: Color::WHITE,
+                                },
+                            },
+                        ],
+                        alignment: TextAlignment {
+                            horizontal: HorizontalAlign::Center,
+                            ..Default::default()
+                        },
+                    },
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(WaveBanner);
+        });
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(80.0), Val::Px(12.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.0),
+                    left: Val::Percent(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            color: Color::rgb(0.1, 0.1, 0.1).into(),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(BossHealthBar)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    color: Color::rgb(0.85, 0.15, 0.2).into(),
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(BossHealthFill);
+        });
+
+    // Keep the strong handle around for the menu screens.
+    commands.insert_resource(UiFont(iosevka));
 }
 
 fn draw_score(time: Res<Time>, mut query: QuerySet<(ScoreQ, HiScoreQ)>, score: Res<HighScore>) {
@@ -130,3 +264,78 @@ fn draw_score(time: Res<Time>, mut query: QuerySet<(ScoreQ, HiScoreQ)>, score: R
         };
     }
 }
+
+fn draw_lives(state: Res<PlayerState>, mut query: Query<&mut Text, With<LivesDisplay>>) {
+    if state.is_changed() {
+        for mut text in query.iter_mut() {
+            text.sections[1].value = state.lives().to_string();
+        }
+    }
+}
+
+fn draw_wave_banner(
+    state: Res<State<GameState>>,
+    sim_time: Res<SimTime>,
+    waves: Res<WaveManager>,
+    mut query: Query<(&mut Text, &mut Visibility), With<WaveBanner>>,
+) {
+    let shown = *state.current() == GameState::Playing && waves.in_break(sim_time.ticks());
+
+    for (mut text, mut visibility) in query.iter_mut() {
+        visibility.is_visible = shown;
+
+        if shown {
+            text.sections[0].value = format!("Wave {}", waves.number());
+            text.sections[1].value = match waves.last_bonus() {
+                0 => String::new(),
+                bonus => format!("\nClear bonus +{}", bonus),
+            };
+        }
+    }
+}
+
+fn draw_boss_health(
+    boss_query: Query<&Health, With<Boss>>,
+    mut bar_query: Query<&mut Visibility, (With<BossHealthBar>, Without<BossHealthFill>)>,
+    mut fill_query: Query<(&mut Style, &mut Visibility), With<BossHealthFill>>,
+) {
+    let health = boss_query.get_single().ok().map(Health::fraction);
+
+    for mut visibility in bar_query.iter_mut() {
+        visibility.is_visible = health.is_some();
+    }
+
+    for (mut style, mut visibility) in fill_query.iter_mut() {
+        visibility.is_visible = health.is_some();
+
+        if let Some(health) = health {
+            style.size.width = Val::Percent(health * 100.0);
+        }
+    }
+}
+
+/// Gives the scores left by a downed UFO their text.
+fn label_floating_scores(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    query: Query<(Entity, &FloatingScore, &Transform), Added<FloatingScore>>,
+) {
+    for (entity, floating, transform) in query.iter() {
+        commands.entity(entity).insert_bundle(Text2dBundle {
+            text: Text::with_section(
+                floating.points.to_string(),
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 20.0,
+                    color: Color::YELLOW,
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: *transform,
+            ..Default::default()
+        });
+    }
+}
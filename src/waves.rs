@@ -0,0 +1,152 @@
+use crate::{
+    config::{GameConfig, GameMode, WaveDef},
+    enemies::ActiveEnemies,
+    scores::HighScore,
+    sim::{SimTime, SIMULATION},
+    GameState,
+};
+
+use bevy::prelude::*;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaveManager::default())
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_waves))
+            .add_system_to_stage(SIMULATION, advance_waves);
+    }
+}
+
+// -- Resources --
+/// Runs the numbered waves of a game. Kept after the run ends so the menus
+/// can show the wave reached.
+#[derive(Default)]
+pub struct WaveManager {
+    number: u32,
+    wave: WaveDef,
+    spawned: u32,
+    /// Tick of the latest spawn. Enemies only join `ActiveEnemies` on the tick after.
+    last_spawn: u64,
+    /// Tick the wave starts spawning at; the break before it runs until then.
+    starts_at: u64,
+    /// Bonus awarded for the wave just cleared, shown during the break.
+    last_bonus: u32,
+    /// Boss of the wave, until it spawns.
+    boss: Option<usize>,
+    boss_alive: bool,
+}
+
+impl WaveManager {
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn wave(&self) -> &WaveDef {
+        &self.wave
+    }
+
+    pub fn last_bonus(&self) -> u32 {
+        self.last_bonus
+    }
+
+    pub fn in_break(&self, now: u64) -> bool {
+        now < self.starts_at
+    }
+
+    /// Whether the next enemy of the wave spawns on tick `now`.
+    pub fn can_spawn(&self, now: u64, active: u32) -> bool {
+        !self.in_break(now)
+            && self.spawned < self.wave.enemies
+            && active < self.wave.max_active
+            && (now - self.starts_at) % self.wave.spawn_interval == 0
+    }
+
+    pub fn count_spawn(&mut self, now: u64) {
+        self.spawned += 1;
+        self.last_spawn = now;
+    }
+
+    /// Enemies of the wave still to spawn, none during the break.
+    pub fn pending(&self, now: u64) -> u32 {
+        if self.in_break(now) {
+            0
+        } else {
+            self.wave.enemies - self.spawned
+        }
+    }
+
+    /// Hands out the boss of the wave once its break is over.
+    pub fn take_boss(&mut self, now: u64) -> Option<usize> {
+        if self.in_break(now) {
+            return None;
+        }
+
+        let boss = self.boss.take();
+        self.boss_alive |= boss.is_some();
+        boss
+    }
+
+    pub fn boss_defeated(&mut self) {
+        self.boss_alive = false;
+    }
+
+    /// Ticks between volleys for a kind firing every `base` ticks at wave 1.
+    pub fn fire_interval(&self, base: u64) -> u64 {
+        ((base as f32 / self.wave.fire_rate).round() as u64).max(1)
+    }
+
+    fn cleared(&self, now: u64, active: u32) -> bool {
+        self.spawned == self.wave.enemies
+            && self.boss.is_none()
+            && !self.boss_alive
+            && active == 0
+            && now > self.last_spawn
+    }
+
+    fn start(&mut self, number: u32, config: &GameConfig, now: u64) {
+        self.number = number;
+        self.wave = config.waves.wave(number);
+        self.spawned = 0;
+        self.starts_at = now + config.waves.break_ticks;
+        self.boss = None;
+
+        match config.mode {
+            GameMode::Arcade => {
+                self.boss = config.bosses.for_wave(number);
+
+                // A boss wave is the boss alone.
+                if self.boss.is_some() {
+                    self.wave.enemies = 0;
+                }
+            }
+            // Every wave is one full grid.
+            GameMode::Classic => self.wave.enemies = config.classic.columns * config.classic.rows,
+        }
+    }
+}
+
+fn start_waves(mut waves: ResMut<WaveManager>, config: Res<GameConfig>) {
+    *waves = WaveManager::default();
+    waves.start(1, &config, 0);
+}
+
+fn advance_waves(
+    mut waves: ResMut<WaveManager>,
+    mut score: ResMut<HighScore>,
+    active_enemies: Res<ActiveEnemies>,
+    sim_time: Res<SimTime>,
+    config: Res<GameConfig>,
+) {
+    let now = sim_time.ticks();
+
+    if waves.in_break(now) || !waves.cleared(now, active_enemies.count()) {
+        return;
+    }
+
+    waves.last_bonus = waves.wave.clear_bonus;
+    score.0 += waves.last_bonus;
+
+    let next = waves.number + 1;
+    waves.start(next, &config, now);
+}